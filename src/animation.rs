@@ -2,8 +2,10 @@ use std::time::Duration;
 
 use bevy::prelude::*;
 use bevy_tweening::{
+    component_animator_system,
     lens::{SpriteColorLens, TransformScaleLens},
-    Animator, EaseFunction, RepeatCount, RepeatStrategy, Tween, TweenCompleted,
+    AnimationSystem, Animator, EaseFunction, Lens, RepeatCount, RepeatStrategy, Tween,
+    TweenCompleted,
 };
 
 use crate::{character::player::Player, state::GameState};
@@ -20,6 +22,12 @@ impl Plugin for AnimationPlugin {
             .add_systems(
                 Update,
                 (vanish_event, hit_flash_event, process_tween_events),
+            )
+            // The tweening plugin only animates the components it knows about
+            .add_systems(
+                Update,
+                component_animator_system::<TextureAtlasSprite>
+                    .in_set(AnimationSystem::AnimationUpdate),
             );
     }
 }
@@ -43,6 +51,21 @@ pub struct HitFlashEvent {
     pub entity: Entity,
 }
 
+/// Tweens the color of a sprite from a texture atlas, like [`SpriteColorLens`] does for regular
+/// sprites.
+struct TextureAtlasSpriteColorLens {
+    start: Color,
+    end: Color,
+}
+
+impl Lens<TextureAtlasSprite> for TextureAtlasSpriteColorLens {
+    fn lerp(&mut self, target: &mut TextureAtlasSprite, ratio: f32) {
+        let start: Vec4 = self.start.into();
+        let end: Vec4 = self.end.into();
+        target.color = start.lerp(end, ratio).into();
+    }
+}
+
 #[derive(Component)]
 pub struct Wobble {
     pub start_scale: Vec3,
@@ -135,29 +158,44 @@ fn hit_flash_event(
     mut hit_flash_events: EventReader<HitFlashEvent>,
     marked: Query<&HitFlashMarker>,
     sprite_query: Query<&Sprite>,
+    atlas_sprite_query: Query<&TextureAtlasSprite>,
 ) {
     for event in hit_flash_events.read() {
+        if marked.contains(event.entity) {
+            continue;
+        }
+
         if let Ok(sprite) = sprite_query.get(event.entity) {
-            if !marked.contains(event.entity) {
-                if let Some(mut entity) = commands.get_entity(event.entity) {
-                    entity.insert(HitFlashMarker);
-                }
+            let flash = Tween::new(
+                EaseFunction::SineInOut,
+                Duration::from_millis(75),
+                SpriteColorLens {
+                    start: sprite.color,
+                    end: Color::RED,
+                },
+            )
+            .with_repeat_count(2)
+            .with_repeat_strategy(RepeatStrategy::MirroredRepeat)
+            .with_completed_event(FLASH_COMPLETED);
 
-                let flash = Tween::new(
-                    EaseFunction::SineInOut,
-                    Duration::from_millis(75),
-                    SpriteColorLens {
-                        start: sprite.color,
-                        end: Color::RED,
-                    },
-                )
-                .with_repeat_count(2)
-                .with_repeat_strategy(RepeatStrategy::MirroredRepeat)
-                .with_completed_event(FLASH_COMPLETED);
+            if let Some(mut entity) = commands.get_entity(event.entity) {
+                entity.insert((HitFlashMarker, Animator::new(flash)));
+            }
+        } else if let Ok(sprite) = atlas_sprite_query.get(event.entity) {
+            let flash = Tween::new(
+                EaseFunction::SineInOut,
+                Duration::from_millis(75),
+                TextureAtlasSpriteColorLens {
+                    start: sprite.color,
+                    end: Color::RED,
+                },
+            )
+            .with_repeat_count(2)
+            .with_repeat_strategy(RepeatStrategy::MirroredRepeat)
+            .with_completed_event(FLASH_COMPLETED);
 
-                if let Some(mut entity) = commands.get_entity(event.entity) {
-                    entity.insert(Animator::new(flash));
-                }
+            if let Some(mut entity) = commands.get_entity(event.entity) {
+                entity.insert((HitFlashMarker, Animator::new(flash)));
             }
         }
     }
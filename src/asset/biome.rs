@@ -0,0 +1,83 @@
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt},
+    prelude::*,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::asset_id;
+
+/// Describes a region of the world, what grows in it and what lives in it.
+/// Biomes are placed using two noise maps (moisture and ruggedness), both of which range from
+/// -1.0 to 1.0. A biome appears wherever both values fall into its ranges.
+#[derive(Asset, Clone, TypePath, Debug, Deserialize)]
+pub struct BiomeData {
+    /// The name of the file the biome was loaded from, without the extension.
+    #[serde(skip)]
+    pub id: String,
+
+    pub name: String,
+
+    pub moisture: (f32, f32),
+    pub ruggedness: (f32, f32),
+
+    /// The average amount of trees placed in a single generation cell.
+    pub tree_density: f32,
+    /// The average amount of rocks placed in a single generation cell.
+    pub rock_density: f32,
+
+    /// Which sprites from the tree atlas can appear in this biome.
+    pub tree_indices: Vec<usize>,
+    /// Which sprites from the rock atlas can appear in this biome.
+    pub rock_indices: Vec<usize>,
+
+    /// The ids of NPCs that are allowed to spawn in this biome.
+    pub npcs: Vec<String>,
+}
+
+impl BiomeData {
+    pub fn contains(&self, moisture: f32, ruggedness: f32) -> bool {
+        moisture >= self.moisture.0
+            && moisture <= self.moisture.1
+            && ruggedness >= self.ruggedness.0
+            && ruggedness <= self.ruggedness.1
+    }
+}
+
+#[derive(Default)]
+pub struct BiomeDataLoader;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum BiomeDataLoaderError {
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [RON](ron) Error
+    #[error("Could not parse RON: {0}")]
+    RonSpannedError(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for BiomeDataLoader {
+    type Asset = BiomeData;
+    type Settings = ();
+    type Error = BiomeDataLoaderError;
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+
+            let mut asset = ron::de::from_bytes::<BiomeData>(&bytes)?;
+            asset.id = asset_id(load_context);
+            Ok(asset)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["biome"]
+    }
+}
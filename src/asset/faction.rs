@@ -0,0 +1,98 @@
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt},
+    prelude::*,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::asset_id;
+
+/// How the members of a faction treat the members of another one.
+/// The order goes from the friendliest to the most hostile relation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub enum Relation {
+    /// Allies can't hurt each other.
+    Ally,
+    /// Neutral characters leave each other alone, but can still fight when provoked.
+    #[default]
+    Neutral,
+    /// Hostile NPCs go after the members of the faction on their own.
+    Hostile,
+}
+
+/// A group of characters that share how they treat other characters.
+#[derive(Asset, Clone, TypePath, Debug, Deserialize)]
+pub struct FactionData {
+    /// The name of the file the faction was loaded from, without the extension.
+    /// NPCs refer to factions by this id.
+    #[serde(skip)]
+    pub id: String,
+
+    /// How the faction treats other factions. Factions that aren't listed are neutral, unless
+    /// they list a more hostile relation themselves.
+    #[serde(default)]
+    pub relations: Vec<(String, Relation)>,
+}
+
+impl FactionData {
+    /// The relation the faction declares toward the other faction, if it declares any.
+    pub fn declared_relation(&self, other: &str) -> Option<Relation> {
+        self.relations
+            .iter()
+            .find(|(faction, _)| faction == other)
+            .map(|(_, relation)| *relation)
+    }
+
+    /// Returns every problem with the faction, e.g. a relation to a faction that doesn't exist.
+    pub fn problems(&self, faction_exists: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for (faction, _) in self.relations.iter() {
+            if !faction_exists(faction) {
+                problems.push(format!(
+                    "it has a relation to an unknown faction \"{faction}\""
+                ));
+            }
+        }
+
+        problems
+    }
+}
+
+#[derive(Default)]
+pub struct FactionDataLoader;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum FactionDataLoaderError {
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [RON](ron) Error
+    #[error("Could not parse RON: {0}")]
+    RonSpannedError(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for FactionDataLoader {
+    type Asset = FactionData;
+    type Settings = ();
+    type Error = FactionDataLoaderError;
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+
+            let mut asset = ron::de::from_bytes::<FactionData>(&bytes)?;
+            asset.id = asset_id(load_context);
+            Ok(asset)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["faction"]
+    }
+}
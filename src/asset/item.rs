@@ -0,0 +1,94 @@
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt},
+    prelude::*,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::combat::defense::Defense;
+
+use super::{asset_id, npc::ProjectileData};
+
+/// Describes an item that can be carried in an inventory.
+#[derive(Asset, Clone, TypePath, Debug, Deserialize)]
+pub struct ItemData {
+    /// The name of the file the item was loaded from, without the extension.
+    /// Inventories and other data files refer to items by this id.
+    #[serde(skip)]
+    pub id: String,
+
+    pub name: String,
+    pub description: String,
+    pub sprite_name: String,
+
+    /// How many of the item fit into a single inventory slot.
+    pub max_stack: u32,
+
+    /// What consuming the item restores. Items without it can't be consumed.
+    #[serde(default)]
+    pub consumable: Option<Consumable>,
+
+    /// How the item attacks when it's equipped. Items without it can't be equipped.
+    #[serde(default)]
+    pub weapon: Option<WeaponData>,
+
+    /// The protection the item gives while it's worn. Items without it can't be worn.
+    #[serde(default)]
+    pub armor: Option<Defense>,
+}
+
+/// The attack of a weapon item.
+#[derive(Clone, Debug, Deserialize)]
+pub struct WeaponData {
+    /// The time between two attacks, in seconds.
+    pub attack_speed: f32,
+    pub projectile: ProjectileData,
+}
+
+/// The amounts restored by consuming an item.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Consumable {
+    pub health: f32,
+    pub hunger: f32,
+    pub thirst: f32,
+    pub stamina: f32,
+}
+
+#[derive(Default)]
+pub struct ItemDataLoader;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum ItemDataLoaderError {
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [RON](ron) Error
+    #[error("Could not parse RON: {0}")]
+    RonSpannedError(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for ItemDataLoader {
+    type Asset = ItemData;
+    type Settings = ();
+    type Error = ItemDataLoaderError;
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+
+            let mut asset = ron::de::from_bytes::<ItemData>(&bytes)?;
+            asset.id = asset_id(load_context);
+            Ok(asset)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["item"]
+    }
+}
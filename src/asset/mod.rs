@@ -6,9 +6,25 @@ use bevy::{
 
 use crate::state::GameState;
 
-use self::npc::{NpcData, NpcDataLoader};
+use self::{
+    biome::{BiomeData, BiomeDataLoader},
+    faction::{FactionData, FactionDataLoader},
+    item::{ItemData, ItemDataLoader},
+    npc::{NpcData, NpcDataLoader},
+    recipe::{RecipeData, RecipeDataLoader},
+    structure::{StructureData, StructureDataLoader},
+    survival::{SurvivalData, SurvivalDataLoader},
+    world::{WorldData, WorldDataLoader},
+};
 
+pub mod biome;
+pub mod faction;
+pub mod item;
 pub mod npc;
+pub mod recipe;
+pub mod structure;
+pub mod survival;
+pub mod world;
 
 pub trait LoadEntity {
     type ExtraData;
@@ -19,7 +35,7 @@ pub trait LoadEntity {
         game_sprites: &mut ResMut<GameSprites>,
         asset_server: &Res<AssetServer>,
         additional: &Self::ExtraData,
-    );
+    ) -> Entity;
 }
 
 pub struct AssetPlugin;
@@ -28,6 +44,20 @@ impl Plugin for AssetPlugin {
     fn build(&self, app: &mut App) {
         app.init_asset::<NpcData>()
             .init_asset_loader::<NpcDataLoader>()
+            .init_asset::<BiomeData>()
+            .init_asset_loader::<BiomeDataLoader>()
+            .init_asset::<ItemData>()
+            .init_asset_loader::<ItemDataLoader>()
+            .init_asset::<RecipeData>()
+            .init_asset_loader::<RecipeDataLoader>()
+            .init_asset::<SurvivalData>()
+            .init_asset_loader::<SurvivalDataLoader>()
+            .init_asset::<StructureData>()
+            .init_asset_loader::<StructureDataLoader>()
+            .init_asset::<FactionData>()
+            .init_asset_loader::<FactionDataLoader>()
+            .init_asset::<WorldData>()
+            .init_asset_loader::<WorldDataLoader>()
             .add_systems(PreStartup, prepare_title_screen)
             .add_systems(OnEnter(GameState::LoadingAssets), prepare_assets)
             .add_systems(
@@ -51,8 +81,9 @@ pub struct EnvironmentAssets {
     pub rock_atlas: Handle<TextureAtlas>,
 }
 
+/// Keeps track of the data folders that have to be loaded before the world can be prepared.
 #[derive(Resource)]
-pub struct FolderTracker(Handle<LoadedFolder>);
+pub struct FolderTracker(Vec<Handle<LoadedFolder>>);
 
 impl GameSprites {
     pub fn get_or_load(
@@ -73,6 +104,16 @@ impl GameSprites {
     }
 }
 
+/// Returns the file name of the asset that is being loaded, without the extension.
+/// Used as an identifier that other data files can refer to.
+pub fn asset_id(load_context: &bevy::asset::LoadContext) -> String {
+    load_context
+        .path()
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
 fn prepare_title_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
     let title_image = asset_server.load("usg-title.png");
     commands.insert_resource(TitleImage(title_image));
@@ -116,7 +157,23 @@ fn prepare_assets(
     });
 
     let npc_folder = asset_server.load_folder("npcs");
-    commands.insert_resource(FolderTracker(npc_folder));
+    let biome_folder = asset_server.load_folder("biomes");
+    let item_folder = asset_server.load_folder("items");
+    let survival_folder = asset_server.load_folder("survival");
+    let recipe_folder = asset_server.load_folder("recipes");
+    let structure_folder = asset_server.load_folder("structures");
+    let faction_folder = asset_server.load_folder("factions");
+    let world_folder = asset_server.load_folder("world");
+    commands.insert_resource(FolderTracker(vec![
+        npc_folder,
+        biome_folder,
+        item_folder,
+        survival_folder,
+        recipe_folder,
+        structure_folder,
+        faction_folder,
+        world_folder,
+    ]));
 
     commands.insert_resource(game_sprites);
 }
@@ -127,15 +184,13 @@ fn check_loading(
     mut game_state: ResMut<NextState<GameState>>,
     folder_tracker: Res<FolderTracker>,
 ) {
-    if let Some(load_state) =
-        asset_server.get_recursive_dependency_load_state(folder_tracker.0.clone())
-    {
-        match load_state {
-            RecursiveDependencyLoadState::Loaded => {
-                commands.remove_resource::<FolderTracker>();
-                game_state.set(GameState::PreparingWorld);
-            }
-            _ => {}
-        }
+    let all_loaded = folder_tracker.0.iter().all(|folder| {
+        asset_server.get_recursive_dependency_load_state(folder.clone())
+            == Some(RecursiveDependencyLoadState::Loaded)
+    });
+
+    if all_loaded {
+        commands.remove_resource::<FolderTracker>();
+        game_state.set(GameState::PreparingWorld);
     }
 }
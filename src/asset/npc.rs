@@ -3,24 +3,38 @@ use bevy::{
     prelude::*,
 };
 use bevy_rapier2d::prelude::*;
-use rand::Rng;
 use serde::Deserialize;
 use thiserror::Error;
 
 use crate::{
     animation::WobbleBundle,
     character::{
+        behavior::{BehaviorNode, FleeData, NpcBehavior},
         npc::{NpcController, NpcKind},
         Character, ProjectileShooter,
     },
-    combat::{self, Immunity, ProjectileStats, ENEMY_GROUP, PROJECTILE_GROUP},
-    world::WorldObject,
+    combat::{
+        self,
+        behavior::ProjectileBehavior,
+        defense::{DamageType, Defense},
+        faction::Faction,
+        melee::MeleeData,
+        pattern::ProjectilePattern,
+        status::{StatusEffect, StatusEffects},
+        Immunity, ProjectileStats, ENEMY_GROUP, PROJECTILE_GROUP,
+    },
+    inventory::loot::LootTable,
+    world::{chunk::ChunkCoord, clock::TimeOfDay, navigation::NpcPath, WorldObject},
 };
 
-use super::{GameSprites, LoadEntity};
+use super::{asset_id, GameSprites, LoadEntity};
 
 #[derive(Asset, Clone, TypePath, Debug, Deserialize)]
 pub struct NpcData {
+    /// The name of the file the NPC was loaded from, without the extension.
+    #[serde(skip)]
+    pub id: String,
+
     pub sprite_name: String,
     pub sprite_size: Vec2,
     pub sprite_color: Color,
@@ -29,12 +43,67 @@ pub struct NpcData {
     pub min_difficulty: f32,
     pub max_difficulty: Option<f32>,
 
+    /// The times of day the NPC can spawn at. NPCs without any can spawn at any time.
+    #[serde(default)]
+    pub spawn_times: Vec<TimeOfDay>,
+
     pub max_health: f32,
+    /// Armor and resistances that reduce the damage the NPC takes.
+    #[serde(default)]
+    pub defense: Defense,
     pub attack_speed: f32,
     pub projectile_stats: ProjectileData,
 
     pub speed: f32,
     pub kind: NpcKind,
+    /// The id of the faction the NPC belongs to. It decides who the NPC fights.
+    pub faction: String,
+    /// How the NPC decides what to do. NPCs without one act like their kind.
+    #[serde(default)]
+    pub behavior: Option<BehaviorNode>,
+    /// When the NPC runs away. NPCs without it only flee when their behavior tree says so.
+    #[serde(default)]
+    pub flee: Option<FleeData>,
+
+    /// What the NPC drops when it dies. NPCs without a loot table don't drop anything.
+    #[serde(default)]
+    pub loot: LootTable,
+}
+
+impl ProjectileData {
+    /// Turns the data into the stats projectiles are spawned with.
+    pub fn stats(&self) -> ProjectileStats {
+        ProjectileStats {
+            damage: self.damage,
+            knockback: self.knockback,
+            speed: self.speed,
+            life_time: Timer::from_seconds(self.life_time, TimerMode::Once),
+            scale: self.scale,
+            color: self.color,
+            damage_type: self.damage_type,
+            effects: self.effects.clone(),
+            behavior: self.behavior.clone(),
+        }
+    }
+}
+
+impl NpcData {
+    pub fn can_spawn_at(&self, time_of_day: TimeOfDay) -> bool {
+        self.spawn_times.is_empty() || self.spawn_times.contains(&time_of_day)
+    }
+
+    /// The NPC's behavior tree, with fleeing put in front when the NPC flees.
+    pub fn behavior(&self) -> BehaviorNode {
+        let behavior = self
+            .behavior
+            .clone()
+            .unwrap_or_else(|| self.kind.default_behavior());
+
+        match &self.flee {
+            Some(flee) => flee.wrap(behavior),
+            None => behavior,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -45,10 +114,33 @@ pub struct ProjectileData {
     pub life_time: f32,
     pub scale: f32,
     pub color: Color,
+    #[serde(default)]
+    pub damage_type: DamageType,
+    #[serde(default)]
+    pub effects: Vec<StatusEffect>,
+    /// How the projectiles are fired. Shooters fire a single projectile per attack without it.
+    #[serde(default)]
+    pub pattern: ProjectilePattern,
+    #[serde(default)]
+    pub behavior: ProjectileBehavior,
+    /// Makes the attack a swing instead of a projectile.
+    #[serde(default)]
+    pub melee: Option<MeleeData>,
+}
+
+/// The id of the [`NpcData`] an NPC was spawned from.
+#[derive(Component, Clone)]
+pub struct NpcId(pub String);
+
+/// Everything needed to spawn an NPC that isn't described by its [`NpcData`].
+pub struct NpcSpawnInfo {
+    pub position: Vec2,
+    /// How long the NPC waits before picking a new wander target.
+    pub wander_delay: f32,
 }
 
 impl LoadEntity for NpcData {
-    type ExtraData = Vec2;
+    type ExtraData = NpcSpawnInfo;
 
     fn load_entity(
         &self,
@@ -56,7 +148,7 @@ impl LoadEntity for NpcData {
         game_sprites: &mut ResMut<GameSprites>,
         asset_server: &Res<AssetServer>,
         additional: &Self::ExtraData,
-    ) {
+    ) -> Entity {
         let npc = commands
             .spawn((
                 SpriteBundle {
@@ -65,7 +157,7 @@ impl LoadEntity for NpcData {
                         color: self.sprite_color,
                         ..Default::default()
                     },
-                    transform: Transform::from_translation(additional.extend(0.0)),
+                    transform: Transform::from_translation(additional.position.extend(0.0)),
                     texture: game_sprites.get_or_load(&self.sprite_name, asset_server),
                     ..Default::default()
                 },
@@ -88,35 +180,43 @@ impl LoadEntity for NpcData {
                     damp: 5.0,
                 },
                 WobbleBundle::new(Vec3::ONE),
-                Immunity(Timer::from_seconds(0.25, TimerMode::Once)),
-                ProjectileShooter {
-                    attack_speed: Timer::from_seconds(self.attack_speed, TimerMode::Once),
-                    projectile_stats: ProjectileStats {
-                        damage: self.projectile_stats.damage,
-                        knockback: self.projectile_stats.knockback,
-                        speed: self.projectile_stats.speed,
-                        life_time: Timer::from_seconds(
-                            self.projectile_stats.life_time,
-                            TimerMode::Once,
+                (
+                    Immunity(Timer::from_seconds(0.25, TimerMode::Once)),
+                    self.defense.clone(),
+                    StatusEffects::default(),
+                    ProjectileShooter {
+                        attack_speed: Timer::from_seconds(self.attack_speed, TimerMode::Once),
+                        projectile_stats: self.projectile_stats.stats(),
+                        pattern: self.projectile_stats.pattern.clone(),
+                        melee: self.projectile_stats.melee.clone(),
+                        burst: None,
+                    },
+                ),
+                (
+                    NpcController {
+                        target: None,
+                        attacker: None,
+                        home: None,
+                        target_change: Timer::from_seconds(
+                            additional.wander_delay,
+                            TimerMode::Repeating,
                         ),
-                        scale: self.projectile_stats.scale,
-                        color: self.projectile_stats.color,
+                        kind: self.kind.clone(),
                     },
-                },
-                NpcController {
-                    target: None,
-                    target_change: Timer::from_seconds(
-                        rand::thread_rng().gen_range(5.0..15.0),
-                        TimerMode::Repeating,
-                    ),
-                    kind: self.kind.clone(),
-                },
+                    NpcBehavior(self.behavior()),
+                    NpcPath::default(),
+                ),
+                NpcId(self.id.clone()),
+                Faction(self.faction.clone()),
+                ChunkCoord::from_position(additional.position),
                 WorldObject,
             ))
             .id();
 
         let healthbar_offset = (-self.collider_halfextents.y) - 20.0;
         combat::healthbar::spawn_healthbar(commands, Vec2::new(0.0, healthbar_offset), npc);
+
+        npc
     }
 }
 
@@ -141,13 +241,14 @@ impl AssetLoader for NpcDataLoader {
         &'a self,
         reader: &'a mut bevy::asset::io::Reader,
         _settings: &'a Self::Settings,
-        _load_context: &'a mut bevy::asset::LoadContext,
+        load_context: &'a mut bevy::asset::LoadContext,
     ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
         Box::pin(async move {
             let mut bytes = Vec::new();
             reader.read_to_end(&mut bytes).await?;
 
-            let asset = ron::de::from_bytes::<NpcData>(&bytes)?;
+            let mut asset = ron::de::from_bytes::<NpcData>(&bytes)?;
+            asset.id = asset_id(load_context);
             Ok(asset)
         })
     }
@@ -0,0 +1,100 @@
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt},
+    prelude::*,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::crafting::WorkstationKind;
+
+use super::asset_id;
+
+/// Describes how items are turned into other items.
+#[derive(Asset, Clone, TypePath, Debug, Deserialize)]
+pub struct RecipeData {
+    /// The name of the file the recipe was loaded from, without the extension.
+    #[serde(skip)]
+    pub id: String,
+
+    pub name: String,
+
+    /// The item ids and amounts used up by crafting.
+    pub inputs: Vec<(String, u32)>,
+    /// The item ids and amounts received after crafting.
+    pub outputs: Vec<(String, u32)>,
+
+    /// The workstation the player has to stand next to. Recipes without one can be crafted
+    /// anywhere.
+    #[serde(default)]
+    pub workstation: Option<WorkstationKind>,
+
+    /// How long crafting takes, in seconds.
+    pub craft_time: f32,
+}
+
+impl RecipeData {
+    /// Returns every problem with the recipe, e.g. items that don't exist.
+    /// `item_exists` is used to look up item ids, since items are loaded separately.
+    pub fn problems(&self, item_exists: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.outputs.is_empty() {
+            problems.push("it has no outputs".to_string());
+        }
+
+        if self.craft_time < 0.0 {
+            problems.push("the craft time is negative".to_string());
+        }
+
+        for (item, amount) in self.inputs.iter().chain(self.outputs.iter()) {
+            if !item_exists(item) {
+                problems.push(format!("it refers to an unknown item \"{item}\""));
+            }
+
+            if *amount == 0 {
+                problems.push(format!("the amount of \"{item}\" is zero"));
+            }
+        }
+
+        problems
+    }
+}
+
+#[derive(Default)]
+pub struct RecipeDataLoader;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum RecipeDataLoaderError {
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [RON](ron) Error
+    #[error("Could not parse RON: {0}")]
+    RonSpannedError(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for RecipeDataLoader {
+    type Asset = RecipeData;
+    type Settings = ();
+    type Error = RecipeDataLoaderError;
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+
+            let mut asset = ron::de::from_bytes::<RecipeData>(&bytes)?;
+            asset.id = asset_id(load_context);
+
+            Ok(asset)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["recipe"]
+    }
+}
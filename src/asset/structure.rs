@@ -0,0 +1,106 @@
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt},
+    prelude::*,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::crafting::WorkstationKind;
+
+use super::asset_id;
+
+/// Describes a structure the player can build by placing an item.
+#[derive(Asset, Clone, TypePath, Debug, Deserialize)]
+pub struct StructureData {
+    /// The name of the file the structure was loaded from, without the extension.
+    /// Stored chunks refer to structures by this id.
+    #[serde(skip)]
+    pub id: String,
+
+    pub name: String,
+
+    /// The id of the item that's used up when placing the structure.
+    pub item: String,
+
+    pub sprite_name: String,
+    pub sprite_size: Vec2,
+    pub collider_halfextents: Vec2,
+
+    pub max_health: f32,
+
+    #[serde(default)]
+    pub kind: StructureKind,
+}
+
+/// What a structure does besides blocking the way.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+pub enum StructureKind {
+    #[default]
+    Wall,
+    /// Can be opened and closed by the player.
+    Door,
+    /// Allows crafting the recipes that require the workstation.
+    Workstation(WorkstationKind),
+    /// Holds an inventory with the given amount of slots.
+    Chest { slots: usize },
+}
+
+impl StructureData {
+    /// Returns every problem with the structure, e.g. an item that doesn't exist.
+    /// `item_exists` is used to look up item ids, since items are loaded separately.
+    pub fn problems(&self, item_exists: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if !item_exists(&self.item) {
+            problems.push(format!("it refers to an unknown item \"{}\"", self.item));
+        }
+
+        if self.max_health <= 0.0 {
+            problems.push("the max health isn't positive".to_string());
+        }
+
+        if let StructureKind::Chest { slots: 0 } = self.kind {
+            problems.push("the chest has no slots".to_string());
+        }
+
+        problems
+    }
+}
+
+#[derive(Default)]
+pub struct StructureDataLoader;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum StructureDataLoaderError {
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [RON](ron) Error
+    #[error("Could not parse RON: {0}")]
+    RonSpannedError(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for StructureDataLoader {
+    type Asset = StructureData;
+    type Settings = ();
+    type Error = StructureDataLoaderError;
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+
+            let mut asset = ron::de::from_bytes::<StructureData>(&bytes)?;
+            asset.id = asset_id(load_context);
+            Ok(asset)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["structure"]
+    }
+}
@@ -0,0 +1,75 @@
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt},
+    prelude::*,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::asset_id;
+
+/// Describes the survival needs of a character, e.g. how quickly it gets hungry.
+/// All rates are per second.
+#[derive(Asset, Clone, TypePath, Debug, Deserialize)]
+pub struct SurvivalData {
+    /// The name of the file the data was loaded from, without the extension.
+    #[serde(skip)]
+    pub id: String,
+
+    pub hunger: NeedData,
+    pub thirst: NeedData,
+
+    pub max_stamina: f32,
+    /// How much stamina sprinting uses up.
+    pub sprint_drain: f32,
+    /// How much stamina comes back while not sprinting.
+    pub stamina_recovery: f32,
+    /// How much faster sprinting is than walking.
+    pub sprint_multiplier: f32,
+
+    /// The damage taken while starving or dying of thirst.
+    pub starvation_damage: f32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NeedData {
+    pub max: f32,
+    pub drain: f32,
+}
+
+#[derive(Default)]
+pub struct SurvivalDataLoader;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum SurvivalDataLoaderError {
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [RON](ron) Error
+    #[error("Could not parse RON: {0}")]
+    RonSpannedError(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for SurvivalDataLoader {
+    type Asset = SurvivalData;
+    type Settings = ();
+    type Error = SurvivalDataLoaderError;
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+
+            let mut asset = ron::de::from_bytes::<SurvivalData>(&bytes)?;
+            asset.id = asset_id(load_context);
+            Ok(asset)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["survival"]
+    }
+}
@@ -0,0 +1,57 @@
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt},
+    prelude::*,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::asset_id;
+
+/// Settings that shape a whole world, e.g. how long its days last.
+#[derive(Asset, Clone, TypePath, Debug, Deserialize)]
+pub struct WorldData {
+    /// The name of the file the data was loaded from, without the extension.
+    #[serde(skip)]
+    pub id: String,
+
+    /// How long a full day lasts, in seconds.
+    pub day_length: f32,
+}
+
+#[derive(Default)]
+pub struct WorldDataLoader;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum WorldDataLoaderError {
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [RON](ron) Error
+    #[error("Could not parse RON: {0}")]
+    RonSpannedError(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for WorldDataLoader {
+    type Asset = WorldData;
+    type Settings = ();
+    type Error = WorldDataLoaderError;
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut bevy::asset::io::Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+
+            let mut asset = ron::de::from_bytes::<WorldData>(&bytes)?;
+            asset.id = asset_id(load_context);
+            Ok(asset)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["world"]
+    }
+}
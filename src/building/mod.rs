@@ -0,0 +1,653 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animation::{HitFlashEvent, VanishEvent},
+    asset::{
+        item::ItemData,
+        structure::{StructureData, StructureKind},
+        GameSprites,
+    },
+    character::player::{self, Player},
+    combat::{started_collision, Projectile},
+    crafting::Workstation,
+    inventory::{pickup, Inventory, ItemRegistry},
+    state::GameState,
+    world::{
+        chunk::ChunkCoord, navigation::NavObstacle, placement::PlacementGrid, seed::WorldRng,
+        WorldObject,
+    },
+};
+
+pub struct BuildingPlugin;
+
+/// The size of the grid structures snap to.
+pub const BUILD_GRID_SIZE: f32 = 64.0;
+
+/// The radius structures take up in the [`PlacementGrid`].
+/// Structures on neighbouring grid cells touch, but don't overlap.
+pub const STRUCTURE_FOOTPRINT: f32 = BUILD_GRID_SIZE / 2.0;
+
+/// How far from the player structures can be placed.
+const BUILD_RANGE: f32 = 300.0;
+
+/// How close the player has to stand to a door or chest to use it.
+const INTERACT_RANGE: f32 = 150.0;
+
+/// How far from a destroyed chest its contents are scattered.
+const DROP_SCATTER: f32 = 40.0;
+
+const GHOST_FREE_COLOR: Color = Color::rgba(0.5, 1.0, 0.5, 0.6);
+const GHOST_BLOCKED_COLOR: Color = Color::rgba(1.0, 0.4, 0.4, 0.6);
+const OPEN_DOOR_ALPHA: f32 = 0.4;
+
+impl Plugin for BuildingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BuildMode>()
+            .add_systems(
+                OnEnter(GameState::PreparingWorld),
+                (prepare_structure_registry, reset_build_mode),
+            )
+            .add_systems(
+                Update,
+                (
+                    build_mode_input,
+                    update_ghost,
+                    place_structure,
+                    interact_input,
+                    update_doors,
+                    structure_hits,
+                )
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .register_type::<Structure>()
+            .register_type::<Door>();
+    }
+}
+
+/// Every valid structure, sorted by id.
+#[derive(Resource, Default)]
+pub struct StructureRegistry {
+    structures: Vec<StructureData>,
+}
+
+impl StructureRegistry {
+    pub fn get(&self, id: &str) -> Option<&StructureData> {
+        self.structures.iter().find(|structure| structure.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StructureData> {
+        self.structures.iter()
+    }
+}
+
+/// The structure the player is currently placing. Nothing is being placed while it's `None`.
+#[derive(Resource, Default)]
+pub struct BuildMode {
+    pub structure: Option<String>,
+}
+
+impl BuildMode {
+    pub fn is_active(&self) -> bool {
+        self.structure.is_some()
+    }
+}
+
+/// Marks a structure placed by the player, so that it can be stored when its chunk unloads.
+#[derive(Reflect, Component)]
+pub struct Structure {
+    /// The id of the [`StructureData`] the structure was built from.
+    pub id: String,
+    pub health: f32,
+}
+
+/// Open doors let everything pass through them.
+#[derive(Reflect, Component)]
+pub struct Door {
+    pub open: bool,
+}
+
+/// The semi-transparent preview of the structure that's about to be placed.
+#[derive(Component)]
+struct Ghost {
+    structure: String,
+}
+
+/// A structure that is kept in memory while the chunk it belongs to is unloaded.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StoredStructure {
+    pub id: String,
+    pub position: Vec2,
+    pub health: f32,
+    #[serde(default)]
+    pub open: bool,
+    /// The contents of a chest.
+    #[serde(default)]
+    pub inventory: Option<Inventory>,
+}
+
+impl StoredStructure {
+    pub fn new(
+        structure: &Structure,
+        transform: &Transform,
+        door: Option<&Door>,
+        inventory: Option<&Inventory>,
+    ) -> Self {
+        Self {
+            id: structure.id.clone(),
+            position: transform.translation.truncate(),
+            health: structure.health,
+            open: door.is_some_and(|door| door.open),
+            inventory: inventory.cloned(),
+        }
+    }
+}
+
+/// Snaps a world position to the center of the build grid cell it's in.
+pub fn snap_to_grid(position: Vec2) -> Vec2 {
+    ((position / BUILD_GRID_SIZE).floor() + 0.5) * BUILD_GRID_SIZE
+}
+
+pub fn spawn_structure(
+    commands: &mut Commands,
+    game_sprites: &mut ResMut<GameSprites>,
+    asset_server: &Res<AssetServer>,
+    placement_grid: &mut PlacementGrid,
+    data: &StructureData,
+    position: Vec2,
+) -> Entity {
+    placement_grid.insert(position, STRUCTURE_FOOTPRINT);
+
+    let structure = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(data.sprite_size),
+                ..Default::default()
+            },
+            transform: Transform::from_translation(position.extend(-1.0)),
+            texture: game_sprites.get_or_load(&data.sprite_name, asset_server),
+            ..Default::default()
+        })
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(
+            data.collider_halfextents.x,
+            data.collider_halfextents.y,
+        ))
+        .insert(Structure {
+            id: data.id.clone(),
+            health: data.max_health,
+        })
+        .insert(ChunkCoord::from_position(position))
+        .insert(NavObstacle)
+        .insert(Name::new(data.name.clone()))
+        .insert(WorldObject)
+        .id();
+
+    match data.kind {
+        StructureKind::Wall => {}
+        StructureKind::Door => {
+            commands.entity(structure).insert(Door { open: false });
+        }
+        StructureKind::Workstation(kind) => {
+            commands.entity(structure).insert(Workstation(kind));
+        }
+        StructureKind::Chest { slots } => {
+            commands.entity(structure).insert(Inventory::new(slots));
+        }
+    }
+
+    structure
+}
+
+/// Spawns a structure again from the state it was stored in.
+pub fn restore_structure(
+    commands: &mut Commands,
+    game_sprites: &mut ResMut<GameSprites>,
+    asset_server: &Res<AssetServer>,
+    placement_grid: &mut PlacementGrid,
+    data: &StructureData,
+    stored: StoredStructure,
+) -> Entity {
+    let structure = spawn_structure(
+        commands,
+        game_sprites,
+        asset_server,
+        placement_grid,
+        data,
+        stored.position,
+    );
+
+    commands.entity(structure).insert(Structure {
+        id: stored.id,
+        health: stored.health,
+    });
+
+    if data.kind == StructureKind::Door {
+        commands
+            .entity(structure)
+            .insert(Door { open: stored.open });
+    }
+
+    if let Some(inventory) = stored.inventory {
+        commands.entity(structure).insert(inventory);
+    }
+
+    structure
+}
+
+/// Builds the structure registry, reporting and leaving out every structure that has problems.
+fn prepare_structure_registry(
+    mut commands: Commands,
+    structures: Res<Assets<StructureData>>,
+    items: Res<Assets<ItemData>>,
+) {
+    let item_exists = |id: &str| items.iter().any(|(_, item)| item.id == id);
+
+    let mut valid_structures = Vec::new();
+
+    for (_, structure) in structures.iter() {
+        let problems = structure.problems(item_exists);
+
+        if problems.is_empty() {
+            valid_structures.push(structure.clone());
+        } else {
+            println!(
+                "skipping structure \"{}\": {}",
+                structure.id,
+                problems.join(", ")
+            );
+        }
+    }
+
+    valid_structures.sort_by(|a, b| a.id.cmp(&b.id));
+
+    commands.insert_resource(StructureRegistry {
+        structures: valid_structures,
+    });
+}
+
+fn reset_build_mode(mut build_mode: ResMut<BuildMode>) {
+    build_mode.structure = None;
+}
+
+/// Cycles through the structures the player carries the items for. Right clicking stops
+/// building.
+fn build_mode_input(
+    key_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    player_query: Query<&Inventory, With<Player>>,
+    structure_registry: Res<StructureRegistry>,
+    mut build_mode: ResMut<BuildMode>,
+) {
+    if mouse_input.just_pressed(MouseButton::Right) {
+        build_mode.structure = None;
+        return;
+    }
+
+    if !key_input.just_pressed(KeyCode::B) {
+        return;
+    }
+
+    let Ok(inventory) = player_query.get_single() else {
+        return;
+    };
+
+    let buildable = structure_registry
+        .iter()
+        .filter(|structure| inventory.count(&structure.item) > 0)
+        .collect::<Vec<_>>();
+
+    // Picks the structure after the current one, or stops building after the last one
+    let next = match &build_mode.structure {
+        Some(current) => buildable
+            .iter()
+            .skip_while(|structure| structure.id != *current)
+            .nth(1),
+        None => buildable.first(),
+    };
+
+    build_mode.structure = next.map(|structure| structure.id.clone());
+}
+
+/// Checks whether a structure fits at the position without overlapping anything solid or another
+/// structure (including open doors).
+fn can_place(
+    rapier_context: &RapierContext,
+    mut structures: impl Iterator<Item = Vec2>,
+    data: &StructureData,
+    position: Vec2,
+) -> bool {
+    let occupied = structures.any(|structure| structure == position);
+
+    let shape = Collider::cuboid(data.collider_halfextents.x, data.collider_halfextents.y);
+    let blocked = rapier_context
+        .intersection_with_shape(
+            position,
+            0.0,
+            &shape,
+            QueryFilter::default().exclude_sensors(),
+        )
+        .is_some();
+
+    !occupied && !blocked
+}
+
+/// Keeps the preview of the structure that's being placed under the cursor.
+fn update_ghost(
+    mut commands: Commands,
+    mut game_sprites: ResMut<GameSprites>,
+    asset_server: Res<AssetServer>,
+    build_mode: Res<BuildMode>,
+    structure_registry: Res<StructureRegistry>,
+    rapier_context: Res<RapierContext>,
+    mut ghost_query: Query<(Entity, &Ghost, &mut Transform, &mut Sprite)>,
+    structure_query: Query<&Transform, (With<Structure>, Without<Ghost>)>,
+    player_query: Query<&Transform, (With<Player>, Without<Ghost>)>,
+    windows: Query<&Window>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+) {
+    let data = build_mode
+        .structure
+        .as_ref()
+        .and_then(|id| structure_registry.get(id));
+
+    // The ghost gets replaced whenever a different structure is picked
+    for (entity, ghost, _, _) in ghost_query.iter() {
+        if data.is_none_or(|data| data.id != ghost.structure) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    let Some(data) = data else {
+        return;
+    };
+
+    let (camera, camera_transform) = camera.single();
+    let Some(position) =
+        player::cursor_world_position(windows.single(), camera, camera_transform).map(snap_to_grid)
+    else {
+        return;
+    };
+
+    let in_range = player_query
+        .get_single()
+        .is_ok_and(|transform| transform.translation.truncate().distance(position) <= BUILD_RANGE);
+
+    let structures = structure_query
+        .iter()
+        .map(|transform| transform.translation.truncate());
+
+    let color = if in_range && can_place(&rapier_context, structures, data, position) {
+        GHOST_FREE_COLOR
+    } else {
+        GHOST_BLOCKED_COLOR
+    };
+
+    match ghost_query
+        .iter_mut()
+        .find(|(_, ghost, _, _)| ghost.structure == data.id)
+    {
+        Some((_, _, mut transform, mut sprite)) => {
+            transform.translation = position.extend(5.0);
+            sprite.color = color;
+        }
+        None => {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(data.sprite_size),
+                        color,
+                        ..Default::default()
+                    },
+                    transform: Transform::from_translation(position.extend(5.0)),
+                    texture: game_sprites.get_or_load(&data.sprite_name, &asset_server),
+                    ..Default::default()
+                },
+                Ghost {
+                    structure: data.id.clone(),
+                },
+                WorldObject,
+            ));
+        }
+    }
+}
+
+/// Places the structure under the cursor, using up one of its items.
+fn place_structure(
+    mut commands: Commands,
+    mut game_sprites: ResMut<GameSprites>,
+    asset_server: Res<AssetServer>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut build_mode: ResMut<BuildMode>,
+    structure_registry: Res<StructureRegistry>,
+    mut placement_grid: ResMut<PlacementGrid>,
+    rapier_context: Res<RapierContext>,
+    mut player_query: Query<(&Transform, &mut Inventory), With<Player>>,
+    structure_query: Query<&Transform, With<Structure>>,
+    windows: Query<&Window>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+) {
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let Some(data) = build_mode
+        .structure
+        .as_ref()
+        .and_then(|id| structure_registry.get(id))
+    else {
+        return;
+    };
+
+    let Ok((player_transform, mut inventory)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    let (camera, camera_transform) = camera.single();
+    let Some(position) =
+        player::cursor_world_position(windows.single(), camera, camera_transform).map(snap_to_grid)
+    else {
+        return;
+    };
+
+    let structures = structure_query
+        .iter()
+        .map(|transform| transform.translation.truncate());
+
+    if player_transform.translation.truncate().distance(position) > BUILD_RANGE
+        || !can_place(&rapier_context, structures, data, position)
+    {
+        return;
+    }
+
+    if !inventory.remove(&data.item, 1) {
+        build_mode.structure = None;
+        return;
+    }
+
+    spawn_structure(
+        &mut commands,
+        &mut game_sprites,
+        &asset_server,
+        &mut placement_grid,
+        data,
+        position,
+    );
+
+    if inventory.count(&data.item) == 0 {
+        build_mode.structure = None;
+    }
+}
+
+/// Opens and closes the nearest door, or stores the player's items in the nearest chest.
+/// Holding shift takes everything out of the chest instead.
+fn interact_input(
+    key_input: Res<Input<KeyCode>>,
+    item_registry: Res<ItemRegistry>,
+    mut player_query: Query<(&Transform, &mut Inventory), With<Player>>,
+    mut interactable_query: Query<
+        (&Transform, Option<&mut Door>, Option<&mut Inventory>),
+        (With<Structure>, Without<Player>),
+    >,
+) {
+    if !key_input.just_pressed(KeyCode::F) {
+        return;
+    }
+
+    let Ok((player_transform, mut player_inventory)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    let player_position = player_transform.translation.truncate();
+
+    let nearest = interactable_query
+        .iter_mut()
+        .filter(|(_, door, inventory)| door.is_some() || inventory.is_some())
+        .map(|(transform, door, inventory)| {
+            let distance = transform.translation.truncate().distance(player_position);
+            (distance, door, inventory)
+        })
+        .filter(|(distance, _, _)| *distance <= INTERACT_RANGE)
+        .min_by(|(a, _, _), (b, _, _)| a.total_cmp(b));
+
+    let Some((_, door, chest)) = nearest else {
+        return;
+    };
+
+    if let Some(mut door) = door {
+        door.open = !door.open;
+    }
+
+    if let Some(mut chest) = chest {
+        let (from, to) = if key_input.pressed(KeyCode::ShiftLeft) {
+            (&mut *chest, &mut *player_inventory)
+        } else {
+            (&mut *player_inventory, &mut *chest)
+        };
+
+        for slot in from.slots.iter_mut() {
+            let Some(stack) = slot else {
+                continue;
+            };
+
+            let max_stack = item_registry
+                .get(&stack.item)
+                .map(|item| item.max_stack)
+                .unwrap_or(1);
+
+            stack.amount = to.add(&stack.item, stack.amount, max_stack);
+
+            if stack.amount == 0 {
+                *slot = None;
+            }
+        }
+    }
+}
+
+/// Turns the collisions of doors off while they're open.
+fn update_doors(
+    mut commands: Commands,
+    mut door_query: Query<(Entity, &Door, &Structure, &mut Sprite), Changed<Door>>,
+    structure_registry: Res<StructureRegistry>,
+) {
+    for (entity, door, structure, mut sprite) in door_query.iter_mut() {
+        let Some(data) = structure_registry.get(&structure.id) else {
+            continue;
+        };
+
+        if door.open {
+            sprite.color.set_a(OPEN_DOOR_ALPHA);
+            commands.entity(entity).remove::<Collider>();
+        } else {
+            sprite.color.set_a(1.0);
+            commands.entity(entity).insert(Collider::cuboid(
+                data.collider_halfextents.x,
+                data.collider_halfextents.y,
+            ));
+        }
+    }
+}
+
+/// Damages structures hit by projectiles of NPCs. Destroyed chests spill their contents.
+fn structure_hits(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut game_sprites: ResMut<GameSprites>,
+    asset_server: Res<AssetServer>,
+    item_registry: Res<ItemRegistry>,
+    mut placement_grid: ResMut<PlacementGrid>,
+    mut world_rng: ResMut<WorldRng>,
+    projectile_query: Query<&Projectile>,
+    player_query: Query<(), With<Player>>,
+    mut structure_query: Query<(&mut Structure, &Transform, Option<&Inventory>)>,
+    mut hit_flash_writer: EventWriter<HitFlashEvent>,
+    mut vanish_writer: EventWriter<VanishEvent>,
+) {
+    for event in collision_events.read() {
+        let Some((projectile_entity, structure_entity)) =
+            started_collision(event, |entity| projectile_query.contains(entity))
+        else {
+            continue;
+        };
+        let Ok(projectile) = projectile_query.get(projectile_entity) else {
+            continue;
+        };
+
+        // The player's own projectiles are blocked by structures, but don't damage them
+        if player_query.contains(projectile.owner) {
+            continue;
+        }
+
+        let Ok((mut structure, transform, inventory)) = structure_query.get_mut(structure_entity)
+        else {
+            continue;
+        };
+
+        if structure.health <= 0.0 {
+            continue;
+        }
+
+        structure.health -= projectile.stats.damage;
+        hit_flash_writer.send(HitFlashEvent {
+            entity: structure_entity,
+        });
+
+        if structure.health > 0.0 {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+
+        for stack in inventory
+            .iter()
+            .flat_map(|inventory| inventory.slots.iter().flatten())
+        {
+            if let Some(item) = item_registry.get(&stack.item) {
+                let offset = Vec2::new(
+                    world_rng.spawning.gen_range(-DROP_SCATTER..DROP_SCATTER),
+                    world_rng.spawning.gen_range(-DROP_SCATTER..DROP_SCATTER),
+                );
+
+                pickup::spawn_pickup(
+                    &mut commands,
+                    &mut game_sprites,
+                    &asset_server,
+                    item,
+                    stack.amount,
+                    position + offset,
+                );
+            }
+        }
+
+        // The structure is gone for good, so it shouldn't be stored with its chunk anymore
+        commands
+            .entity(structure_entity)
+            .remove::<(Structure, Collider)>();
+        placement_grid.remove(position);
+        vanish_writer.send(VanishEvent {
+            entity: structure_entity,
+        });
+    }
+}
@@ -0,0 +1,571 @@
+//! Behavior trees that drive NPCs.
+//!
+//! Every frame, an NPC's tree is evaluated from the root. Conditions check something about the
+//! NPC and its surroundings, actions decide where it moves and whether it attacks, and composites
+//! combine them. Every node reports a [`Status`]:
+//!
+//! - a [`Sequence`](BehaviorNode::Sequence) runs its children in order as long as they succeed,
+//! - a [`Selector`](BehaviorNode::Selector) runs its children in order until one doesn't fail.
+//!
+//! Trees are written in the `behavior` field of `.npc` files, e.g. an archer that keeps its
+//! distance and runs away when it's hurt:
+//!
+//! ```ron
+//! behavior: Some(Selector([
+//!     Sequence([HealthBelow(0.3), FindTarget, Flee(400.0)]),
+//!     Sequence([FindTarget, Succeed(Attack), KeepDistance(220.0)]),
+//!     Wander,
+//! ])),
+//! ```
+//!
+//! NPCs without a tree use the one of their [`NpcKind`]. NPCs that declare when they flee in their
+//! `flee` field do so before anything else their tree has them do, see [`FleeData`].
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng};
+use serde::Deserialize;
+
+use crate::{
+    combat::faction::{FactionRegistry, PLAYER_FACTION},
+    world::navigation::{NavGrid, NpcPath},
+};
+
+use super::{
+    direction_to,
+    npc::{NpcController, NpcKind, NpcTarget},
+};
+
+/// How much the parts of a candidate's score weigh when NPCs pick a target. See [`target_score`].
+const PROXIMITY_WEIGHT: f32 = 1.0;
+const WOUNDED_WEIGHT: f32 = 0.5;
+const CURRENT_TARGET_WEIGHT: f32 = 0.35;
+const ATTACKER_WEIGHT: f32 = 0.75;
+
+/// How far NPCs wander from where they are.
+const WANDER_DISTANCE: f32 = 250.0;
+/// How many random spots NPCs try before they give up on wandering until the next target change.
+const WANDER_ATTEMPTS: usize = 4;
+/// How close NPCs have to get to a spot before it counts as reached.
+const ARRIVAL_DISTANCE: f32 = 7.5;
+
+/// How far ahead fleeing NPCs aim when running away.
+const FLEE_DISTANCE: f32 = 200.0;
+/// The angles fleeing NPCs try in turn when the way straight away from the threat is blocked, in
+/// degrees.
+const FLEE_ANGLES: [f32; 7] = [0.0, 45.0, -45.0, 90.0, -90.0, 135.0, -135.0];
+
+/// How much closer than the wanted distance a target may get before the NPC backs off.
+const KEEP_DISTANCE_TOLERANCE: f32 = 0.8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    Failure,
+    /// The node is busy, e.g. an NPC on its way somewhere.
+    Running,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub enum BehaviorNode {
+    // Composites
+    /// Runs the children in order until one fails or is running.
+    Sequence(Vec<BehaviorNode>),
+    /// Runs the children in order until one succeeds or is running.
+    Selector(Vec<BehaviorNode>),
+
+    // Decorators
+    /// Succeeds when the child fails and the other way around.
+    Not(Box<BehaviorNode>),
+    /// Runs the child, but succeeds even if the child fails.
+    Succeed(Box<BehaviorNode>),
+
+    // Conditions
+    /// Whether the NPC's health is below the given fraction of its max health.
+    HealthBelow(f32),
+    /// Whether the NPC has a character or structure to fight.
+    HasTarget,
+    /// Whether the target is closer than the given distance.
+    TargetInRange(f32),
+    /// Whether the target is close enough for the NPC's attack to hit it.
+    TargetInAttackRange,
+    /// Whether someone hurt the NPC and is still around.
+    Attacked,
+    IsNight,
+
+    // Actions
+    /// Picks the most attractive character of a hostile faction nearby, or whoever attacked the
+    /// NPC. Fails when there's nobody to fight.
+    FindTarget,
+    /// Like [`FindTarget`](BehaviorNode::FindTarget), but only picks whoever attacked the NPC.
+    FindAttacker,
+    /// Picks the closest structure nearby, as long as the NPC is hostile to the player who built
+    /// it.
+    FindStructure,
+    /// Moves toward the target.
+    Chase,
+    /// Attacks the target. Fails when it's out of range.
+    Attack,
+    /// Stays about the given distance away from the target.
+    KeepDistance(f32),
+    /// Runs away from whoever attacked the NPC, or from its target, steering around obstacles.
+    /// Fails once the NPC is further away than the given distance, and the NPC stops worrying about
+    /// the threat.
+    Flee(f32),
+    /// Walks back to where the NPC was spawned when it's further away than the given distance.
+    ReturnHome(f32),
+    /// Walks to random spots nearby every now and then.
+    Wander,
+    /// Stands still.
+    Idle,
+}
+
+impl NpcKind {
+    /// The tree used by NPCs of the kind that don't declare their own.
+    pub fn default_behavior(&self) -> BehaviorNode {
+        use BehaviorNode::*;
+
+        let fight = |find: BehaviorNode| Sequence(vec![find, Succeed(Box::new(Attack)), Chase]);
+
+        match self {
+            NpcKind::Friendly => Selector(vec![fight(FindAttacker), Wander]),
+            NpcKind::Hostile | NpcKind::VeryHostile => {
+                Selector(vec![fight(FindTarget), fight(FindStructure), Wander])
+            }
+        }
+    }
+}
+
+/// When an NPC runs away, declared in the `flee` field of `.npc` files.
+/// NPCs flee when either of the triggers applies, e.g. prey that runs from whoever hurts it:
+///
+/// ```ron
+/// flee: Some((when_attacked: true)),
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct FleeData {
+    /// The NPC flees when its health drops below this fraction of its max health.
+    pub health_below: f32,
+    /// The NPC flees from whoever hurts it instead of fighting back.
+    pub when_attacked: bool,
+    /// How far away from the threat the NPC has to get before it feels safe.
+    pub safe_distance: f32,
+}
+
+impl Default for FleeData {
+    fn default() -> Self {
+        Self {
+            health_below: 0.0,
+            when_attacked: false,
+            safe_distance: 400.0,
+        }
+    }
+}
+
+impl FleeData {
+    /// Puts fleeing in front of the given tree, so that it takes priority over everything else.
+    pub fn wrap(&self, behavior: BehaviorNode) -> BehaviorNode {
+        use BehaviorNode::*;
+
+        let mut triggers = vec![HealthBelow(self.health_below)];
+
+        if self.when_attacked {
+            triggers.push(Attacked);
+        }
+
+        Selector(vec![
+            Sequence(vec![Selector(triggers), Flee(self.safe_distance)]),
+            behavior,
+        ])
+    }
+}
+
+/// The behavior tree of an NPC.
+#[derive(Component, Clone)]
+pub struct NpcBehavior(pub BehaviorNode);
+
+/// A character an NPC could pick as its target, as seen at the start of the update.
+pub struct Candidate {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub faction: String,
+    /// The character's health compared to its max health, from 0 to 1.
+    pub health: f32,
+}
+
+/// How attractive a candidate is as a target, the NPC goes after the one with the highest score.
+/// Close and wounded candidates are preferred, the NPC's current target and whoever attacked it
+/// get a bonus, so that NPCs don't keep switching between targets that are about as close.
+fn target_score(
+    candidate: &Candidate,
+    distance: f32,
+    chase_range: f32,
+    is_current_target: bool,
+    is_attacker: bool,
+) -> f32 {
+    let mut score = PROXIMITY_WEIGHT * (1.0 - distance / chase_range).max(0.0)
+        + WOUNDED_WEIGHT * (1.0 - candidate.health.clamp(0.0, 1.0));
+
+    if is_current_target {
+        score += CURRENT_TARGET_WEIGHT;
+    }
+
+    if is_attacker {
+        score += ATTACKER_WEIGHT;
+    }
+
+    score
+}
+
+/// Everything a behavior tree can look at and change while it's evaluated for a single NPC.
+pub struct BehaviorContext<'a> {
+    pub entity: Entity,
+    pub position: Vec2,
+    /// The NPC's health compared to its max health, from 0 to 1.
+    pub health: f32,
+    pub faction: &'a str,
+    /// How far away the NPC notices targets and how far it chases them. Very hostile NPCs chase
+    /// the player no matter how far away they are.
+    pub chase_range: f32,
+    /// How close targets have to be for the NPC's attack to hit them.
+    pub attack_range: f32,
+    pub is_night: bool,
+    pub delta: Duration,
+
+    pub controller: &'a mut NpcController,
+    pub path: &'a mut NpcPath,
+    pub candidates: &'a [Candidate],
+    pub structures: &'a [(Entity, Vec2)],
+    pub faction_registry: &'a FactionRegistry,
+    pub nav_grid: &'a NavGrid,
+    pub rng: &'a mut StdRng,
+
+    /// The direction the NPC moves in. It stands still and slows down while it's `None`.
+    pub input: Option<Vec2>,
+    /// Where the NPC attacks this frame, if it attacks.
+    pub attack: Option<Vec2>,
+}
+
+impl BehaviorContext<'_> {
+    /// The position of the character or structure the NPC is fighting.
+    fn target_position(&self) -> Option<Vec2> {
+        let Some(NpcTarget::Character(target)) = self.controller.target else {
+            return None;
+        };
+
+        self.position_of(target)
+    }
+
+    fn position_of(&self, entity: Entity) -> Option<Vec2> {
+        self.candidates
+            .iter()
+            .find(|candidate| candidate.entity == entity)
+            .map(|candidate| candidate.position)
+            .or_else(|| {
+                self.structures
+                    .iter()
+                    .find(|(structure, _)| *structure == entity)
+                    .map(|(_, position)| *position)
+            })
+    }
+
+    fn move_toward(&mut self, target: Vec2) {
+        self.input = Some(
+            self.path
+                .steer(self.nav_grid, self.position, target, self.delta),
+        );
+    }
+
+    /// Forgets about the character or structure the NPC was fighting.
+    fn clear_target(&mut self) {
+        if let Some(NpcTarget::Character(_)) = self.controller.target {
+            self.controller.target = None;
+        }
+    }
+
+    fn find_target(&mut self, only_attacker: bool) -> Status {
+        let current_target = match self.controller.target {
+            Some(NpcTarget::Character(entity)) => Some(entity),
+            _ => None,
+        };
+        let attacker = self.controller.attacker;
+        // Very hostile NPCs hunt the player down from anywhere
+        let hunts_player = !only_attacker && matches!(self.controller.kind, NpcKind::VeryHostile);
+
+        let best_candidate = self
+            .candidates
+            .iter()
+            .filter(|candidate| candidate.entity != self.entity)
+            .filter(|candidate| {
+                let is_enemy = !only_attacker
+                    && self
+                        .faction_registry
+                        .is_hostile(self.faction, &candidate.faction);
+
+                is_enemy || attacker == Some(candidate.entity)
+            })
+            .filter_map(|candidate| {
+                let distance = self.position.distance(candidate.position);
+
+                let in_range = distance < self.chase_range
+                    || (hunts_player && candidate.faction == PLAYER_FACTION);
+
+                in_range.then(|| {
+                    let score = target_score(
+                        candidate,
+                        distance,
+                        self.chase_range,
+                        current_target == Some(candidate.entity),
+                        attacker == Some(candidate.entity),
+                    );
+                    (candidate.entity, score)
+                })
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        match best_candidate {
+            Some((target, _)) => {
+                self.controller.target = Some(NpcTarget::Character(target));
+                Status::Success
+            }
+            None => {
+                self.clear_target();
+                Status::Failure
+            }
+        }
+    }
+
+    fn find_structure(&mut self) -> Status {
+        if !self
+            .faction_registry
+            .is_hostile(self.faction, PLAYER_FACTION)
+        {
+            return Status::Failure;
+        }
+
+        let closest_structure = self
+            .structures
+            .iter()
+            .map(|(entity, position)| (*entity, self.position.distance(*position)))
+            .filter(|(_, distance)| *distance < self.chase_range)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        match closest_structure {
+            Some((structure, _)) => {
+                self.controller.target = Some(NpcTarget::Character(structure));
+                Status::Success
+            }
+            None => Status::Failure,
+        }
+    }
+
+    fn flee(&mut self, safe_distance: f32) -> Status {
+        let threat = match self.controller.target {
+            Some(NpcTarget::Flee(threat)) => Some(threat),
+            Some(NpcTarget::Character(target)) => Some(self.controller.attacker.unwrap_or(target)),
+            _ => self.controller.attacker,
+        };
+
+        let Some(threat) = threat else {
+            return Status::Failure;
+        };
+
+        let threat_position = self
+            .position_of(threat)
+            .filter(|threat_position| self.position.distance(*threat_position) < safe_distance);
+
+        // The threat is gone or far enough away
+        let Some(threat_position) = threat_position else {
+            if let Some(NpcTarget::Flee(_)) = self.controller.target {
+                self.controller.target = None;
+            }
+            if self.controller.attacker == Some(threat) {
+                self.controller.attacker = None;
+            }
+            return Status::Failure;
+        };
+
+        self.controller.target = Some(NpcTarget::Flee(threat));
+
+        let escape = self.escape_point(direction_to(threat_position, self.position));
+        self.move_toward(escape);
+
+        Status::Running
+    }
+
+    /// The spot a fleeing NPC runs toward. NPCs prefer running straight away from the threat, but
+    /// veer off when something is in the way.
+    fn escape_point(&self, away: Vec2) -> Vec2 {
+        let points = FLEE_ANGLES.iter().map(|angle| {
+            self.position + Vec2::from_angle(angle.to_radians()).rotate(away) * FLEE_DISTANCE
+        });
+
+        points
+            .clone()
+            .find(|point| {
+                !self.nav_grid.is_blocked(*point)
+                    && self.nav_grid.is_clear_line(self.position, *point)
+            })
+            .or_else(|| {
+                points
+                    .clone()
+                    .find(|point| !self.nav_grid.is_blocked(*point))
+            })
+            .unwrap_or(self.position + away * FLEE_DISTANCE)
+    }
+
+    fn wander(&mut self) -> Status {
+        if self.controller.target_change.just_finished() {
+            if let Some(NpcTarget::Position(_)) = self.controller.target {
+                self.controller.target = None;
+            } else {
+                self.pick_wander_target();
+            }
+        }
+
+        let Some(NpcTarget::Position(wander_target)) = self.controller.target else {
+            self.input = None;
+            return Status::Running;
+        };
+
+        if self.position.distance(wander_target) > ARRIVAL_DISTANCE {
+            self.move_toward(wander_target);
+        } else {
+            self.controller.target = None;
+            self.input = None;
+        }
+
+        Status::Running
+    }
+
+    /// Wandering NPCs only pick spots they can actually get to.
+    fn pick_wander_target(&mut self) {
+        let position = self.position;
+
+        for _ in 0..WANDER_ATTEMPTS {
+            let x = self
+                .rng
+                .gen_range(position.x - WANDER_DISTANCE..position.x + WANDER_DISTANCE);
+            let y = self
+                .rng
+                .gen_range(position.y - WANDER_DISTANCE..position.y + WANDER_DISTANCE);
+            let wander_target = Vec2::new(x, y);
+
+            let reachable = !self.nav_grid.is_blocked(wander_target)
+                && (self.nav_grid.is_clear_line(position, wander_target)
+                    || self.nav_grid.find_path(position, wander_target).is_some());
+
+            if reachable {
+                self.controller.target = Some(NpcTarget::Position(wander_target));
+                self.path.clear();
+                return;
+            }
+        }
+    }
+
+    pub fn tick(&mut self, node: &BehaviorNode) -> Status {
+        match node {
+            BehaviorNode::Sequence(children) => {
+                for child in children {
+                    let status = self.tick(child);
+
+                    if status != Status::Success {
+                        return status;
+                    }
+                }
+
+                Status::Success
+            }
+            BehaviorNode::Selector(children) => {
+                for child in children {
+                    let status = self.tick(child);
+
+                    if status != Status::Failure {
+                        return status;
+                    }
+                }
+
+                Status::Failure
+            }
+            BehaviorNode::Not(child) => match self.tick(child) {
+                Status::Success => Status::Failure,
+                Status::Failure => Status::Success,
+                Status::Running => Status::Running,
+            },
+            BehaviorNode::Succeed(child) => match self.tick(child) {
+                Status::Running => Status::Running,
+                _ => Status::Success,
+            },
+            BehaviorNode::HealthBelow(fraction) => condition(self.health < *fraction),
+            BehaviorNode::HasTarget => condition(self.target_position().is_some()),
+            BehaviorNode::TargetInRange(distance) => condition(
+                self.target_position()
+                    .is_some_and(|target| self.position.distance(target) < *distance),
+            ),
+            BehaviorNode::TargetInAttackRange => condition(
+                self.target_position()
+                    .is_some_and(|target| self.position.distance(target) < self.attack_range),
+            ),
+            BehaviorNode::Attacked => condition(self.controller.attacker.is_some()),
+            BehaviorNode::IsNight => condition(self.is_night),
+            BehaviorNode::FindTarget => self.find_target(false),
+            BehaviorNode::FindAttacker => self.find_target(true),
+            BehaviorNode::FindStructure => self.find_structure(),
+            BehaviorNode::Chase => match self.target_position() {
+                Some(target) => {
+                    self.move_toward(target);
+                    Status::Running
+                }
+                None => Status::Failure,
+            },
+            BehaviorNode::Attack => match self.target_position() {
+                Some(target) if self.position.distance(target) < self.attack_range => {
+                    self.attack = Some(target);
+                    Status::Success
+                }
+                _ => Status::Failure,
+            },
+            BehaviorNode::KeepDistance(distance) => {
+                let Some(target) = self.target_position() else {
+                    return Status::Failure;
+                };
+
+                let current_distance = self.position.distance(target);
+
+                if current_distance < distance * KEEP_DISTANCE_TOLERANCE {
+                    let away = direction_to(target, self.position);
+                    self.move_toward(self.position + away * (distance - current_distance));
+                } else if current_distance > *distance {
+                    self.move_toward(target);
+                } else {
+                    self.input = None;
+                }
+
+                Status::Running
+            }
+            BehaviorNode::Flee(safe_distance) => self.flee(*safe_distance),
+            BehaviorNode::ReturnHome(distance) => match self.controller.home {
+                Some(home) if self.position.distance(home) > *distance => {
+                    self.move_toward(home);
+                    Status::Running
+                }
+                _ => Status::Failure,
+            },
+            BehaviorNode::Wander => self.wander(),
+            BehaviorNode::Idle => {
+                self.input = None;
+                Status::Running
+            }
+        }
+    }
+}
+
+fn condition(value: bool) -> Status {
+    if value {
+        Status::Success
+    } else {
+        Status::Failure
+    }
+}
@@ -1,39 +1,56 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use bevy_tweening::Lerp;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     animation::VanishEvent,
-    combat::{ProjectileStats, SpawnProjectileEvent},
+    combat::{
+        melee::{MeleeData, Swing},
+        pattern::{Burst, ProjectilePattern},
+        status::StatusEffects,
+        ProjectileStats, SpawnProjectileEvent,
+    },
     state::GameState,
+    world::seed::WorldRng,
 };
 
+use self::survival::{Hunger, Stamina, Thirst};
+
+pub mod behavior;
 pub mod npc;
 pub mod player;
+pub mod survival;
 
 pub struct CharacterPlugin;
 
 impl Plugin for CharacterPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((npc::NpcPlugin, player::PlayerPlugin))
-            .add_event::<ShootEvent>()
-            .add_systems(
-                FixedUpdate,
-                move_characters.run_if(in_state(GameState::InGame)),
-            )
-            .add_systems(
-                Update,
-                (
-                    character_update,
-                    shoot_events,
-                    shooter_cooldown,
-                    health_regen_update,
-                )
-                    .run_if(in_state(GameState::InGame)),
+        app.add_plugins((
+            npc::NpcPlugin,
+            player::PlayerPlugin,
+            survival::SurvivalPlugin,
+        ))
+        .add_event::<ShootEvent>()
+        .add_systems(
+            FixedUpdate,
+            move_characters.run_if(in_state(GameState::InGame)),
+        )
+        .add_systems(
+            Update,
+            (
+                character_update,
+                shoot_events,
+                fire_bursts,
+                shooter_cooldown,
+                health_regen_update,
             )
-            .register_type::<HealthRegen>()
-            .register_type::<Character>()
-            .register_type::<ProjectileShooter>();
+                .run_if(in_state(GameState::InGame)),
+        )
+        .register_type::<HealthRegen>()
+        .register_type::<Character>()
+        .register_type::<ProjectileShooter>();
     }
 }
 
@@ -48,9 +65,28 @@ pub struct ShootEvent {
 pub struct ProjectileShooter {
     pub projectile_stats: ProjectileStats,
     pub attack_speed: Timer,
+    pub pattern: ProjectilePattern,
+    /// Shooters with melee data swing at their targets instead of firing projectiles.
+    pub melee: Option<MeleeData>,
+    /// The burst that's currently being fired.
+    pub burst: Option<Burst>,
 }
 
-#[derive(Reflect, Component)]
+impl ProjectileShooter {
+    /// How close a target has to be for an attack to have a chance of hitting it.
+    pub fn range(&self) -> f32 {
+        match &self.melee {
+            Some(melee) => melee.range,
+            None => {
+                self.projectile_stats.speed
+                    * self.projectile_stats.life_time.duration().as_secs_f32()
+                    / 25.0
+            }
+        }
+    }
+}
+
+#[derive(Reflect, Component, Clone, Serialize, Deserialize)]
 pub struct Character {
     // Combat
     pub max_health: f32,
@@ -65,22 +101,37 @@ pub struct Character {
     pub damp: f32,
 }
 
-#[derive(Reflect, Component)]
+#[derive(Reflect, Component, Clone, Serialize, Deserialize)]
 pub struct HealthRegen {
     pub delay: Timer,
     pub speed: f32,
 }
 
 fn move_characters(
-    mut character_query: Query<(&mut Character, &mut Transform, &mut Velocity)>,
+    mut character_query: Query<(
+        &mut Character,
+        &mut Transform,
+        &mut Velocity,
+        Option<&Stamina>,
+        Option<&StatusEffects>,
+    )>,
     time: Res<Time>,
 ) {
     let delta = time.delta_seconds();
 
-    for (mut character, mut transform, mut velocity) in character_query.iter_mut() {
+    for (mut character, mut transform, mut velocity, stamina, status_effects) in
+        character_query.iter_mut()
+    {
+        if status_effects.is_some_and(|status_effects| status_effects.is_stunned()) {
+            character.input = Vec2::ZERO;
+        }
+
+        let speed_multiplier = stamina.map_or(1.0, |stamina| stamina.speed_multiplier())
+            * status_effects.map_or(1.0, |status_effects| status_effects.speed_multiplier());
+
         let mut input = character.input.normalize_or_zero();
         let input_axis = input;
-        input *= character.speed * delta;
+        input *= character.speed * speed_multiplier * delta;
 
         if input != Vec2::ZERO {
             if input_axis.x != 0.0 {
@@ -111,10 +162,21 @@ fn character_update(
 }
 
 fn health_regen_update(
-    mut regen_query: Query<(&mut Character, &mut HealthRegen)>,
+    mut regen_query: Query<(
+        &mut Character,
+        &mut HealthRegen,
+        Option<&Hunger>,
+        Option<&Thirst>,
+    )>,
     time: Res<Time>,
 ) {
-    for (mut character, mut health_regen) in regen_query.iter_mut() {
+    for (mut character, mut health_regen, hunger, thirst) in regen_query.iter_mut() {
+        // Starving characters don't heal until they've eaten and the delay has passed again
+        if survival::is_starving(hunger, thirst) {
+            health_regen.delay.reset();
+            continue;
+        }
+
         health_regen.delay.tick(time.delta());
 
         if health_regen.delay.mode() == TimerMode::Repeating {
@@ -130,32 +192,145 @@ fn health_regen_update(
 }
 
 fn shoot_events(
-    mut shooters: Query<(&mut ProjectileShooter, &mut Transform, &mut Collider)>,
+    mut commands: Commands,
+    mut shooters: Query<(
+        &mut ProjectileShooter,
+        &Transform,
+        &Collider,
+        Option<&StatusEffects>,
+        Option<&Swing>,
+    )>,
     mut events: EventReader<ShootEvent>,
     mut spawn_event_writer: EventWriter<SpawnProjectileEvent>,
+    mut world_rng: ResMut<WorldRng>,
 ) {
+    let rng = &mut world_rng.ai;
+
     for event in events.read() {
-        if let Ok((mut shooter, transform, collider)) = shooters.get_mut(event.entity) {
-            if shooter.attack_speed.finished() {
-                let position = transform.translation.truncate();
-                let extents = collider.as_cuboid().unwrap().half_extents();
+        if let Ok((mut shooter, transform, collider, status_effects, swing)) =
+            shooters.get_mut(event.entity)
+        {
+            let stunned = status_effects.is_some_and(|status_effects| status_effects.is_stunned());
+            // A new attack can't start before the burst or swing of the last one is over
+            let busy = shooter.burst.is_some() || swing.is_some();
 
-                let direction = direction_to(position, event.target);
+            if !shooter.attack_speed.finished() || busy || stunned {
+                continue;
+            }
 
-                let position = position + (extents * direction);
+            if let Some(melee) = &shooter.melee {
+                let direction = direction_to(transform.translation.truncate(), event.target);
 
-                spawn_event_writer.send(SpawnProjectileEvent {
-                    caster: event.entity,
-                    projectile_stats: shooter.projectile_stats.clone(),
+                commands.entity(event.entity).insert(Swing::new(
+                    melee,
                     direction,
-                    start_position: position,
-                    target_group: event.target_group,
-                });
+                    event.target_group,
+                ));
+            } else {
+                fire_shot(
+                    event.entity,
+                    &shooter,
+                    transform,
+                    collider,
+                    event.target,
+                    event.target_group,
+                    &mut spawn_event_writer,
+                    rng,
+                );
+
+                let pattern = &shooter.pattern;
+
+                if pattern.burst > 1 {
+                    shooter.burst = Some(Burst {
+                        shots_left: pattern.burst - 1,
+                        delay: Timer::from_seconds(pattern.burst_delay, TimerMode::Repeating),
+                        target: event.target,
+                        target_group: event.target_group,
+                    });
+                }
+            }
+
+            shooter.attack_speed.reset();
+            shooter.attack_speed.unpause();
+        }
+    }
+}
+
+/// Fires the remaining shots of bursts at the spot the burst was aimed at.
+fn fire_bursts(
+    mut shooters: Query<(
+        Entity,
+        &mut ProjectileShooter,
+        &Transform,
+        &Collider,
+        Option<&StatusEffects>,
+    )>,
+    mut spawn_event_writer: EventWriter<SpawnProjectileEvent>,
+    mut world_rng: ResMut<WorldRng>,
+    time: Res<Time>,
+) {
+    let rng = &mut world_rng.ai;
+
+    for (entity, mut shooter, transform, collider, status_effects) in shooters.iter_mut() {
+        let Some(mut burst) = shooter.burst.take() else {
+            continue;
+        };
+
+        // Getting stunned cuts the burst short
+        if status_effects.is_some_and(|status_effects| status_effects.is_stunned()) {
+            continue;
+        }
 
-                shooter.attack_speed.reset();
-                shooter.attack_speed.unpause();
+        burst.delay.tick(time.delta());
+
+        for _ in 0..burst.delay.times_finished_this_tick() {
+            if burst.shots_left == 0 {
+                break;
             }
+
+            fire_shot(
+                entity,
+                &shooter,
+                transform,
+                collider,
+                burst.target,
+                burst.target_group,
+                &mut spawn_event_writer,
+                rng,
+            );
+            burst.shots_left -= 1;
         }
+
+        if burst.shots_left > 0 {
+            shooter.burst = Some(burst);
+        }
+    }
+}
+
+/// Fires a single shot of the shooter's pattern at the target.
+fn fire_shot(
+    entity: Entity,
+    shooter: &ProjectileShooter,
+    transform: &Transform,
+    collider: &Collider,
+    target: Vec2,
+    target_group: u32,
+    spawn_event_writer: &mut EventWriter<SpawnProjectileEvent>,
+    rng: &mut impl Rng,
+) {
+    let position = transform.translation.truncate();
+    let extents = collider.as_cuboid().unwrap().half_extents();
+
+    let aim = direction_to(position, target);
+
+    for direction in shooter.pattern.directions(aim, rng) {
+        spawn_event_writer.send(SpawnProjectileEvent {
+            caster: entity,
+            projectile_stats: shooter.projectile_stats.clone(),
+            direction,
+            start_position: position + (extents * direction),
+            target_group,
+        });
     }
 }
 
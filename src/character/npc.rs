@@ -1,13 +1,26 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
-use rand::prelude::*;
-use serde::Deserialize;
-
-use crate::{combat::PLAYER_GROUP, state::GameState};
-
-use super::{player::Player, Character, ProjectileShooter, ShootEvent};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    building::Structure,
+    combat::faction::{Faction, FactionRegistry},
+    state::GameState,
+    world::{
+        clock::{TimeOfDay, WorldClock},
+        navigation::{NavGrid, NpcPath},
+        seed::WorldRng,
+    },
+};
+
+use super::{
+    behavior::{BehaviorContext, Candidate, NpcBehavior},
+    Character, ProjectileShooter, ShootEvent,
+};
 
 const CHASE_RANGE: f32 = 250.0;
+/// How much further NPCs notice and chase their targets at night.
+const NIGHT_CHASE_MULTIPLIER: f32 = 1.5;
 
 pub struct NpcPlugin;
 
@@ -17,131 +30,175 @@ impl Plugin for NpcPlugin {
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize)]
 pub struct NpcController {
     pub kind: NpcKind,
     pub target: Option<NpcTarget>,
     pub target_change: Timer,
+    /// The character that last hurt the NPC. Every NPC fights back, even against characters it
+    /// would otherwise leave alone.
+    #[serde(skip)]
+    pub attacker: Option<Entity>,
+    /// Where the NPC was first seen by its behavior tree.
+    #[serde(default)]
+    pub home: Option<Vec2>,
+}
+
+impl NpcController {
+    /// Returns a copy of the controller that can outlive the current entities, e.g. in a stored
+    /// chunk or a save file. Targeted characters and threats are forgotten, because they may not
+    /// exist anymore by the time the NPC gets spawned again.
+    pub fn detached(&self) -> Self {
+        let mut controller = self.clone();
+
+        if let Some(NpcTarget::Character(_) | NpcTarget::Flee(_)) = controller.target {
+            controller.target = None;
+        }
+        controller.attacker = None;
+
+        controller
+    }
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Serialize, Deserialize)]
 pub enum NpcTarget {
     Position(Vec2),
+    /// Chases and attacks the entity. Besides characters, hostile NPCs also go after structures.
     Character(Entity),
+    /// Runs away from the entity.
+    Flee(Entity),
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// Decides whether the NPC hunts the player down, and how it acts when its data doesn't declare a
+/// behavior tree.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum NpcKind {
     /// Friendly NPCs will only attack while provoked
     Friendly,
-    /// Hostile NPCs will start chasing you when you get close
+    /// Hostile NPCs will start chasing characters of hostile factions that get close
     Hostile,
-    /// Very Hostile NPCs will chase you after being spawned
+    /// Very Hostile NPCs also chase the player after being spawned, no matter how far away the
+    /// player is
     VeryHostile,
 }
 
 fn npc_update(
-    mut npc_query: Query<(
-        Entity,
-        &Transform,
-        &mut NpcController,
-        &mut Character,
-        &mut Velocity,
-        &ProjectileShooter,
+    mut character_queries: ParamSet<(
+        Query<(
+            Entity,
+            &Transform,
+            &mut NpcController,
+            &mut Character,
+            &mut Velocity,
+            &ProjectileShooter,
+            &Faction,
+            &mut NpcPath,
+            &NpcBehavior,
+        )>,
+        Query<(Entity, &Transform, &Faction, &Character)>,
     )>,
-    player_query: Query<(Entity, &Transform), With<Player>>,
-    transform_query: Query<&Transform>,
+    structure_query: Query<(Entity, &Transform), With<Structure>>,
     mut shoot_event_writer: EventWriter<ShootEvent>,
+    mut world_rng: ResMut<WorldRng>,
+    world_clock: Res<WorldClock>,
+    faction_registry: Res<FactionRegistry>,
+    nav_grid: Res<NavGrid>,
     time: Res<Time>,
 ) {
-    let mut rng = rand::thread_rng();
-
-    for (npc_entity, transform, mut npc, mut character, mut velocity, projectile_shooter) in
-        npc_query.iter_mut()
+    let rng = &mut world_rng.ai;
+
+    let is_night = world_clock.time_of_day() == TimeOfDay::Night;
+    let chase_range = if is_night {
+        CHASE_RANGE * NIGHT_CHASE_MULTIPLIER
+    } else {
+        CHASE_RANGE
+    };
+
+    let candidates = character_queries
+        .p1()
+        .iter()
+        .map(|(entity, transform, faction, character)| Candidate {
+            entity,
+            position: transform.translation.truncate(),
+            faction: faction.0.clone(),
+            health: character.health / character.max_health,
+        })
+        .collect::<Vec<_>>();
+
+    let structures = structure_query
+        .iter()
+        .map(|(entity, transform)| (entity, transform.translation.truncate()))
+        .collect::<Vec<_>>();
+
+    for (
+        npc_entity,
+        transform,
+        mut npc,
+        mut character,
+        mut velocity,
+        projectile_shooter,
+        faction,
+        mut path,
+        behavior,
+    ) in character_queries.p0().iter_mut()
     {
         npc.target_change.tick(time.delta());
 
-        // NPC Kind-specific behaviour
-        match npc.kind {
-            NpcKind::Hostile => {
-                if let Ok((player_entity, player_transform)) = player_query.get_single() {
-                    let player_pos = player_transform.translation.truncate();
+        let position = transform.translation.truncate();
 
-                    if transform.translation.truncate().distance(player_pos) < CHASE_RANGE {
-                        npc.target = Some(NpcTarget::Character(player_entity));
-                    }
-                }
-            }
-            NpcKind::VeryHostile => {
-                if let Ok((player_entity, _)) = player_query.get_single() {
-                    npc.target = Some(NpcTarget::Character(player_entity));
-                }
-            }
-            NpcKind::Friendly => {}
+        // NPCs remember where they started out, so that they can find their way back
+        if npc.home.is_none() {
+            npc.home = Some(position);
         }
 
-        // Targets
-        if let Some(target) = &npc.target {
-            match target {
-                NpcTarget::Position(target_pos) => {
-                    let position = transform.translation.truncate();
-
-                    if (position.distance(*target_pos)) > 7.5 {
-                        character.input =
-                            super::direction_to(transform.translation.truncate(), *target_pos);
-                    } else {
-                        npc.target = None;
-                    }
-                }
-                NpcTarget::Character(entity) => {
-                    if let Ok(target_transform) = transform_query.get(*entity) {
-                        let target_pos = target_transform.translation.truncate();
-
-                        let position = transform.translation.truncate();
-
-                        character.input = super::direction_to(position, target_pos);
-
-                        // Used to predict whether the projectile could have a chance to hit the target
-                        let possible_range = (projectile_shooter.projectile_stats.speed
-                            * projectile_shooter
-                                .projectile_stats
-                                .life_time
-                                .duration()
-                                .as_secs_f32())
-                            / 25.0;
-
-                        if position.distance(target_pos) < possible_range {
-                            shoot_event_writer.send(ShootEvent {
-                                entity: npc_entity,
-                                target: target_pos,
-                                target_group: PLAYER_GROUP,
-                            });
-                        }
-
-                        if position.distance(target_pos) > CHASE_RANGE {
-                            npc.target = None;
-                        }
-                    }
-                }
-            }
-        } else {
-            character.input = Vec2::ZERO;
-            velocity.linvel = velocity
-                .linvel
-                .lerp(Vec2::ZERO, character.damp * time.delta_seconds());
+        // Forget about attackers that are gone
+        if npc
+            .attacker
+            .is_some_and(|attacker| !candidates.iter().any(|other| other.entity == attacker))
+        {
+            npc.attacker = None;
         }
 
-        if npc.target_change.just_finished() {
-            if let Some(target) = &npc.target {
-                if let NpcTarget::Position(_) = target {
-                    npc.target = None;
-                }
-            } else {
-                let position = transform.translation.truncate();
-                let x = rng.gen_range(position.x - 250.0..position.x + 250.0) as f32;
-                let y = rng.gen_range(position.y - 250.0..position.y + 250.0) as f32;
-                npc.target = Some(NpcTarget::Position(Vec2::new(x, y)));
+        let mut context = BehaviorContext {
+            entity: npc_entity,
+            position,
+            health: character.health / character.max_health,
+            faction: &faction.0,
+            chase_range,
+            attack_range: projectile_shooter.range(),
+            is_night,
+            delta: time.delta(),
+            controller: &mut npc,
+            path: &mut path,
+            candidates: &candidates,
+            structures: &structures,
+            faction_registry: &faction_registry,
+            nav_grid: &nav_grid,
+            rng: &mut *rng,
+            input: None,
+            attack: None,
+        };
+
+        context.tick(&behavior.0);
+
+        let (input, attack) = (context.input, context.attack);
+
+        match input {
+            Some(input) => character.input = input,
+            None => {
+                character.input = Vec2::ZERO;
+                velocity.linvel = velocity
+                    .linvel
+                    .lerp(Vec2::ZERO, character.damp * time.delta_seconds());
             }
         }
+
+        if let Some(target) = attack {
+            shoot_event_writer.send(ShootEvent {
+                entity: npc_entity,
+                target,
+                target_group: faction_registry.attackable_groups(&faction.0),
+            });
+        }
     }
 }
@@ -1,15 +1,25 @@
 use crate::animation::WobbleBundle;
-use crate::asset::GameSprites;
-use crate::character::{Character, ProjectileShooter, ShootEvent};
-use crate::combat::{self, Immunity, ProjectileStats, ENEMY_GROUP, PLAYER_GROUP, PROJECTILE_GROUP};
-use crate::state::GameState;
-use crate::world::{
-    prepare_world, WorldObject, MAX_WORLD_X, MAX_WORLD_Y, MIN_WORLD_X, MIN_WORLD_Y,
+use crate::asset::{item::ItemData, survival::SurvivalData, GameSprites};
+use crate::building::BuildMode;
+use crate::character::{Character, ShootEvent};
+use crate::combat::{
+    self,
+    defense::Defense,
+    faction::{Faction, FactionRegistry, PLAYER_FACTION},
+    status::StatusEffects,
+    Immunity, PLAYER_GROUP, PROJECTILE_GROUP,
+};
+use crate::inventory::{
+    equipment::{self, Equipment},
+    pickup::PICKUP_GROUP,
+    Inventory, ItemRegistry, PLAYER_INVENTORY_SIZE,
 };
+use crate::state::GameState;
+use crate::world::{prepare_world, WorldObject};
 use bevy::prelude::*;
-use bevy_rapier2d::na::clamp;
 use bevy_rapier2d::prelude::*;
 
+use super::survival::{ConsumeItemEvent, Stamina, SurvivalBundle};
 use super::HealthRegen;
 
 pub struct PlayerPlugin;
@@ -24,13 +34,22 @@ impl Plugin for PlayerPlugin {
         .add_systems(
             Update,
             (
-                player_input.run_if(in_state(GameState::InGame)),
+                (player_input, player_consume_input).run_if(in_state(GameState::InGame)),
                 paused_menu_input.run_if(in_state(GameState::Paused)),
             ),
         );
     }
 }
 
+/// The id of the survival data file describing the player's needs.
+const PLAYER_SURVIVAL_DATA: &str = "player";
+
+/// The id of the weapon every new player starts out with.
+pub const STARTING_WEAPON: &str = "stick";
+
+/// Where the player appears when a new world is created.
+pub const PLAYER_SPAWN: Vec2 = Vec2::new(0.0, 100.0);
+
 #[derive(Component)]
 pub struct Player;
 
@@ -38,6 +57,8 @@ fn spawn_player(
     mut commands: Commands,
     mut game_sprites: ResMut<GameSprites>,
     asset_server: Res<AssetServer>,
+    survival_data: Res<Assets<SurvivalData>>,
+    items: Res<Assets<ItemData>>,
 ) {
     let player = commands
         .spawn(SpriteBundle {
@@ -45,7 +66,7 @@ fn spawn_player(
                 custom_size: Some(Vec2::new(120.0, 120.0)),
                 ..Default::default()
             },
-            transform: Transform::from_xyz(0.0, 100.0, 0.0),
+            transform: Transform::from_translation(PLAYER_SPAWN.extend(0.0)),
             texture: game_sprites.get_or_load(&"human-normal.png".to_string(), &asset_server),
             ..Default::default()
         })
@@ -55,7 +76,7 @@ fn spawn_player(
         .insert(LockedAxes::ROTATION_LOCKED)
         .insert(CollisionGroups::new(
             Group::from_bits_truncate(PLAYER_GROUP),
-            Group::from_bits_truncate(PROJECTILE_GROUP | 0b0001),
+            Group::from_bits_truncate(PROJECTILE_GROUP | PICKUP_GROUP | 0b0001),
         ))
         .insert(Character {
             max_health: 30.0,
@@ -67,31 +88,64 @@ fn spawn_player(
             accel: 3.9,
             damp: 5.0,
         })
-        .insert(ProjectileShooter {
-            projectile_stats: ProjectileStats {
-                damage: 4.5,
-                knockback: 8000.0,
-                speed: 25000.0,
-                life_time: Timer::from_seconds(0.20, TimerMode::Once),
-                scale: 1.0,
-                color: Color::WHITE,
-            },
-            attack_speed: Timer::from_seconds(0.5, TimerMode::Once),
-        })
+        // The attack is replaced by the equipped weapon's once the hotbar has been filled
+        .insert(equipment::unarmed_shooter())
         .insert(Immunity(Timer::from_seconds(0.25, TimerMode::Once)))
         .insert(HealthRegen {
             delay: Timer::from_seconds(6.0, TimerMode::Once),
             speed: 0.05,
         })
+        .insert(starting_inventory(&items))
+        .insert(Equipment::default())
+        // Worn armor replaces the defense
+        .insert(Defense::default())
+        .insert(StatusEffects::default())
+        .insert(Faction(PLAYER_FACTION.to_string()))
         .insert(WobbleBundle::new(Vec3::ONE))
         .insert(Name::new("Player"))
         .insert(Player)
         .insert(WorldObject)
         .id();
 
+    match survival_data
+        .iter()
+        .find(|(_, data)| data.id == PLAYER_SURVIVAL_DATA)
+    {
+        Some((_, data)) => {
+            commands.entity(player).insert(SurvivalBundle::new(data));
+        }
+        None => println!("could not find the player's survival data, the player has no needs"),
+    }
+
     combat::healthbar::spawn_healthbar(&mut commands, Vec2::new(0.0, -80.0), player);
 }
 
+/// Resolves the cursor to the position in the world it's pointing at.
+pub fn cursor_world_position(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Option<Vec2> {
+    window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
+}
+
+/// The item registry isn't ready yet while the player is spawned, so the items are looked up in
+/// the loaded assets directly.
+fn starting_inventory(items: &Assets<ItemData>) -> Inventory {
+    let mut inventory = Inventory::new(PLAYER_INVENTORY_SIZE);
+
+    match items.iter().find(|(_, item)| item.id == STARTING_WEAPON) {
+        Some((_, weapon)) => {
+            inventory.add(&weapon.id, 1, weapon.max_stack);
+        }
+        None => println!("could not find the starting weapon \"{STARTING_WEAPON}\""),
+    }
+
+    inventory
+}
+
 fn camera_follow(
     player_query: Query<&Transform, With<Player>>,
     mut camera_query: Query<&mut Transform, (With<Camera>, Without<Player>)>,
@@ -102,18 +156,6 @@ fn camera_follow(
             camera_transform.translation = camera_transform
                 .translation
                 .lerp(player_transform.translation, 1.5 * time.delta_seconds());
-
-            camera_transform.translation.x = clamp(
-                camera_transform.translation.x,
-                MIN_WORLD_X + 150.0,
-                MAX_WORLD_X - 150.0,
-            );
-
-            camera_transform.translation.y = clamp(
-                camera_transform.translation.y,
-                MIN_WORLD_Y - 100.0,
-                MAX_WORLD_Y + 100.0,
-            );
         }
     }
 }
@@ -125,15 +167,17 @@ fn paused_menu_input(key_input: Res<Input<KeyCode>>, mut game_state: ResMut<Next
 }
 
 fn player_input(
-    mut player_query: Query<(Entity, &mut Character), With<Player>>,
+    mut player_query: Query<(Entity, &mut Character, Option<&mut Stamina>), With<Player>>,
     mut shoot_event_writer: EventWriter<ShootEvent>,
     key_input: Res<Input<KeyCode>>,
     mouse_input: Res<Input<MouseButton>>,
+    build_mode: Res<BuildMode>,
+    faction_registry: Res<FactionRegistry>,
     mut game_state: ResMut<NextState<GameState>>,
     windows: Query<&Window>,
     camera: Query<(&Camera, &GlobalTransform)>,
 ) {
-    if let Ok((entity, mut character)) = player_query.get_single_mut() {
+    if let Ok((entity, mut character, stamina)) = player_query.get_single_mut() {
         let window = windows.single();
         let (camera, camera_transform) = camera.single();
 
@@ -155,21 +199,50 @@ fn player_input(
             character.input.x = 1.0;
         }
 
+        if let Some(mut stamina) = stamina {
+            stamina.sprinting = key_input.pressed(KeyCode::ShiftLeft);
+        }
+
         if key_input.just_pressed(KeyCode::Escape) {
             game_state.set(GameState::Paused);
         }
 
-        if mouse_input.pressed(MouseButton::Left) {
-            if let Some(world_position) = window
-                .cursor_position()
-                .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
-            {
+        // Clicking places structures while building
+        if mouse_input.pressed(MouseButton::Left) && !build_mode.is_active() {
+            if let Some(world_position) = cursor_world_position(window, camera, camera_transform) {
                 shoot_event_writer.send(ShootEvent {
                     entity,
                     target: world_position,
-                    target_group: ENEMY_GROUP,
+                    target_group: faction_registry.attackable_groups(PLAYER_FACTION),
                 });
             }
         }
     }
 }
+
+/// Consumes the first consumable item in the player's inventory.
+fn player_consume_input(
+    player_query: Query<(Entity, &Inventory), With<Player>>,
+    item_registry: Res<ItemRegistry>,
+    key_input: Res<Input<KeyCode>>,
+    mut consume_event_writer: EventWriter<ConsumeItemEvent>,
+) {
+    if !key_input.just_pressed(KeyCode::E) {
+        return;
+    }
+
+    if let Ok((entity, inventory)) = player_query.get_single() {
+        let consumable = inventory.slots.iter().flatten().find(|stack| {
+            item_registry
+                .get(&stack.item)
+                .is_some_and(|item| item.consumable.is_some())
+        });
+
+        if let Some(stack) = consumable {
+            consume_event_writer.send(ConsumeItemEvent {
+                entity,
+                item: stack.item.clone(),
+            });
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset::survival::{NeedData, SurvivalData},
+    inventory::{Inventory, ItemRegistry},
+    state::GameState,
+};
+
+use super::Character;
+
+pub struct SurvivalPlugin;
+
+impl Plugin for SurvivalPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ConsumeItemEvent>()
+            .add_systems(
+                Update,
+                (
+                    drain_needs,
+                    starvation_damage,
+                    stamina_update,
+                    consume_item_event,
+                )
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .register_type::<Need>()
+            .register_type::<Hunger>()
+            .register_type::<Thirst>()
+            .register_type::<Stamina>()
+            .register_type::<Starvation>();
+    }
+}
+
+/// A value that slowly drains over time and has to be refilled.
+#[derive(Reflect, Clone, Serialize, Deserialize)]
+pub struct Need {
+    pub value: f32,
+    pub max: f32,
+    /// How much the value drops per second.
+    pub drain: f32,
+}
+
+impl Need {
+    pub fn new(data: &NeedData) -> Self {
+        Self {
+            value: data.max,
+            max: data.max,
+            drain: data.drain,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value <= 0.0
+    }
+
+    pub fn restore(&mut self, amount: f32) {
+        self.value = (self.value + amount).clamp(0.0, self.max);
+    }
+}
+
+#[derive(Reflect, Component, Clone, Serialize, Deserialize)]
+pub struct Hunger(pub Need);
+
+#[derive(Reflect, Component, Clone, Serialize, Deserialize)]
+pub struct Thirst(pub Need);
+
+/// Used up by sprinting and recovered while walking or standing still.
+#[derive(Reflect, Component, Clone, Serialize, Deserialize)]
+pub struct Stamina {
+    pub value: f32,
+    pub max: f32,
+    pub sprint_drain: f32,
+    pub recovery: f32,
+    pub sprint_multiplier: f32,
+    /// Set by whatever controls the character.
+    pub sprinting: bool,
+}
+
+impl Stamina {
+    /// Returns how much faster the character currently moves.
+    pub fn speed_multiplier(&self) -> f32 {
+        if self.sprinting && self.value > 0.0 {
+            self.sprint_multiplier
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Deals damage to characters whose hunger or thirst has run out.
+#[derive(Reflect, Component, Clone, Serialize, Deserialize)]
+pub struct Starvation {
+    pub damage: f32,
+}
+
+#[derive(Bundle)]
+pub struct SurvivalBundle {
+    pub hunger: Hunger,
+    pub thirst: Thirst,
+    pub stamina: Stamina,
+    pub starvation: Starvation,
+}
+
+impl SurvivalBundle {
+    pub fn new(data: &SurvivalData) -> Self {
+        Self {
+            hunger: Hunger(Need::new(&data.hunger)),
+            thirst: Thirst(Need::new(&data.thirst)),
+            stamina: Stamina {
+                value: data.max_stamina,
+                max: data.max_stamina,
+                sprint_drain: data.sprint_drain,
+                recovery: data.stamina_recovery,
+                sprint_multiplier: data.sprint_multiplier,
+                sprinting: false,
+            },
+            starvation: Starvation {
+                damage: data.starvation_damage,
+            },
+        }
+    }
+}
+
+/// Requests an item to be taken out of the entity's inventory and consumed.
+#[derive(Event)]
+pub struct ConsumeItemEvent {
+    pub entity: Entity,
+    pub item: String,
+}
+
+/// Checks whether the entity is starving or dying of thirst.
+pub fn is_starving(hunger: Option<&Hunger>, thirst: Option<&Thirst>) -> bool {
+    hunger.is_some_and(|hunger| hunger.0.is_empty())
+        || thirst.is_some_and(|thirst| thirst.0.is_empty())
+}
+
+fn drain_needs(
+    mut hunger_query: Query<&mut Hunger>,
+    mut thirst_query: Query<&mut Thirst>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+
+    for mut hunger in hunger_query.iter_mut() {
+        let drain = hunger.0.drain;
+        hunger.0.restore(-drain * delta);
+    }
+
+    for mut thirst in thirst_query.iter_mut() {
+        let drain = thirst.0.drain;
+        thirst.0.restore(-drain * delta);
+    }
+}
+
+fn starvation_damage(
+    mut starving_query: Query<(
+        &mut Character,
+        &Starvation,
+        Option<&Hunger>,
+        Option<&Thirst>,
+    )>,
+    time: Res<Time>,
+) {
+    for (mut character, starvation, hunger, thirst) in starving_query.iter_mut() {
+        if is_starving(hunger, thirst) {
+            character.health -= starvation.damage * time.delta_seconds();
+        }
+    }
+}
+
+fn stamina_update(mut stamina_query: Query<(&Character, &mut Stamina)>, time: Res<Time>) {
+    let delta = time.delta_seconds();
+
+    for (character, mut stamina) in stamina_query.iter_mut() {
+        let change = if stamina.sprinting && character.input != Vec2::ZERO {
+            -stamina.sprint_drain
+        } else {
+            stamina.recovery
+        };
+
+        stamina.value = (stamina.value + change * delta).clamp(0.0, stamina.max);
+    }
+}
+
+fn consume_item_event(
+    mut consume_events: EventReader<ConsumeItemEvent>,
+    mut consumer_query: Query<(
+        &mut Inventory,
+        &mut Character,
+        Option<&mut Hunger>,
+        Option<&mut Thirst>,
+        Option<&mut Stamina>,
+    )>,
+    item_registry: Res<ItemRegistry>,
+) {
+    for event in consume_events.read() {
+        let Some(consumable) = item_registry
+            .get(&event.item)
+            .and_then(|item| item.consumable.as_ref())
+        else {
+            println!(
+                "attempted to consume \"{}\", which can't be consumed",
+                event.item
+            );
+            continue;
+        };
+
+        let Ok((mut inventory, mut character, hunger, thirst, stamina)) =
+            consumer_query.get_mut(event.entity)
+        else {
+            continue;
+        };
+
+        if !inventory.remove(&event.item, 1) {
+            continue;
+        }
+
+        character.health = (character.health + consumable.health).min(character.max_health);
+
+        if let Some(mut hunger) = hunger {
+            hunger.0.restore(consumable.hunger);
+        }
+
+        if let Some(mut thirst) = thirst {
+            thirst.0.restore(consumable.thirst);
+        }
+
+        if let Some(mut stamina) = stamina {
+            stamina.value = (stamina.value + consumable.stamina).clamp(0.0, stamina.max);
+        }
+    }
+}
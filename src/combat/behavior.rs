@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+
+/// How far away a homing projectile notices targets.
+const HOMING_RANGE: f32 = 450.0;
+
+/// Optional ways a projectile behaves in flight. They can be combined freely, a projectile with
+/// the default behavior flies straight and vanishes on the first thing it touches.
+#[derive(Reflect, Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ProjectileBehavior {
+    /// How fast the projectile turns toward the closest target, in degrees per second.
+    pub homing: f32,
+    /// How many characters the projectile passes through before it vanishes.
+    pub pierce: u32,
+    /// How many times the projectile bounces off walls and obstacles before it vanishes.
+    pub bounces: u32,
+}
+
+/// Turns the direction toward the closest of the given targets, by at most `max_angle` radians.
+pub fn home_in(
+    position: Vec2,
+    direction: Vec2,
+    max_angle: f32,
+    targets: impl Iterator<Item = Vec2>,
+) -> Vec2 {
+    let closest = targets
+        .filter(|target| target.distance(position) <= HOMING_RANGE)
+        .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)));
+
+    let Some(target) = closest else {
+        return direction;
+    };
+
+    let angle = direction.angle_between(target - position);
+
+    if !angle.is_finite() {
+        return direction;
+    }
+
+    Vec2::from_angle(angle.clamp(-max_angle, max_angle)).rotate(direction)
+}
+
+/// Reflects the direction off the collider it hit.
+/// Sensors don't report contact normals, so the normal is estimated from where the projectile is
+/// compared to the collider: the closest side for boxes, straight outward for everything else.
+pub fn bounce(
+    direction: Vec2,
+    position: Vec2,
+    collider: &Collider,
+    collider_transform: &GlobalTransform,
+) -> Vec2 {
+    let offset = position - collider_transform.translation().truncate();
+
+    let normal = match collider.as_cuboid() {
+        Some(cuboid) => {
+            let relative = offset / cuboid.half_extents();
+
+            if relative.x.abs() > relative.y.abs() {
+                Vec2::new(relative.x.signum(), 0.0)
+            } else {
+                Vec2::new(0.0, relative.y.signum())
+            }
+        }
+        None => offset.normalize_or_zero(),
+    };
+
+    // Projectiles that are already flying away from the surface keep going
+    if normal == Vec2::ZERO || direction.dot(normal) >= 0.0 {
+        return direction;
+    }
+
+    direction - 2.0 * direction.dot(normal) * normal
+}
@@ -0,0 +1,57 @@
+//! Damage mitigation.
+//!
+//! Every hit deals damage of a single [`DamageType`]. The damage a character takes is
+//!
+//! ```text
+//! damage * (1 - resistance) * ARMOR_SCALE / (ARMOR_SCALE + armor)
+//! ```
+//!
+//! - `resistance` is the character's resistance to the damage type, clamped to `-1..=1`.
+//!   0.5 halves the damage, 1 makes the character immune and -0.5 is a weakness that deals 50%
+//!   more damage.
+//! - `armor` reduces every type of damage. Armor equal to [`ARMOR_SCALE`] halves the damage, twice
+//!   as much leaves a third of it and so on, so stacking armor never makes a character
+//!   invulnerable.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The amount of armor that halves incoming damage.
+pub const ARMOR_SCALE: f32 = 10.0;
+
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DamageType {
+    #[default]
+    Slash,
+    Pierce,
+    Blunt,
+    Fire,
+    Frost,
+}
+
+/// How well a character withstands hits. Characters without it take the full damage.
+#[derive(Reflect, Component, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Defense {
+    pub armor: f32,
+    /// The resistances to single damage types. Damage types that aren't listed aren't resisted.
+    pub resistances: Vec<(DamageType, f32)>,
+}
+
+impl Defense {
+    pub fn resistance(&self, damage_type: DamageType) -> f32 {
+        self.resistances
+            .iter()
+            .filter(|(resisted, _)| *resisted == damage_type)
+            .map(|(_, resistance)| resistance)
+            .sum::<f32>()
+            .clamp(-1.0, 1.0)
+    }
+
+    /// Applies the formula described in the [module docs](self) to the damage of a hit.
+    pub fn mitigate(&self, damage: f32, damage_type: DamageType) -> f32 {
+        let armor = self.armor.max(0.0);
+
+        damage * (1.0 - self.resistance(damage_type)) * ARMOR_SCALE / (ARMOR_SCALE + armor)
+    }
+}
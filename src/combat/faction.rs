@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::asset::faction::{FactionData, Relation};
+
+/// The faction the player belongs to.
+pub const PLAYER_FACTION: &str = "player";
+
+/// The collision group of the first faction. Every faction gets its own group after it, so that
+/// projectiles can be filtered by the factions they're allowed to hit.
+const FIRST_FACTION_GROUP: u32 = 1 << 8;
+const MAX_FACTIONS: usize = 32 - 8;
+/// Every collision group that can belong to a faction.
+const FACTION_GROUPS: u32 = !(FIRST_FACTION_GROUP - 1);
+
+/// The faction a character belongs to, by id.
+#[derive(Reflect, Component, Clone, Debug)]
+pub struct Faction(pub String);
+
+/// Every valid faction, sorted by id, except for an implicit player faction that comes last when
+/// the player faction is missing. A faction's collision group depends on its position.
+#[derive(Resource, Default)]
+pub struct FactionRegistry {
+    factions: Vec<FactionData>,
+}
+
+impl FactionRegistry {
+    pub fn get(&self, id: &str) -> Option<&FactionData> {
+        self.factions.iter().find(|faction| faction.id == id)
+    }
+
+    /// The collision group of the faction. Unknown factions don't have any.
+    pub fn group(&self, id: &str) -> u32 {
+        self.factions
+            .iter()
+            .position(|faction| faction.id == id)
+            .map_or(0, |index| FIRST_FACTION_GROUP << index)
+    }
+
+    /// How the members of the factions treat each other. Relations always go both ways: when
+    /// two factions declare different relations toward each other, the more hostile one applies.
+    pub fn relation(&self, faction: &str, other: &str) -> Relation {
+        if faction == other {
+            return Relation::Ally;
+        }
+
+        let declared = |from: &str, to: &str| {
+            self.get(from)
+                .and_then(|faction| faction.declared_relation(to))
+                .unwrap_or_default()
+        };
+
+        declared(faction, other).max(declared(other, faction))
+    }
+
+    pub fn is_hostile(&self, faction: &str, other: &str) -> bool {
+        self.relation(faction, other) == Relation::Hostile
+    }
+
+    /// The collision groups of every faction the members of the given faction can hurt, which
+    /// are all factions that aren't allied with it.
+    pub fn attackable_groups(&self, faction: &str) -> u32 {
+        self.factions
+            .iter()
+            .filter(|other| self.relation(faction, &other.id) != Relation::Ally)
+            .map(|other| self.group(&other.id))
+            .fold(0, |groups, group| groups | group)
+    }
+}
+
+/// Builds the faction registry, reporting and leaving out every faction that has problems.
+pub fn prepare_faction_registry(mut commands: Commands, factions: Res<Assets<FactionData>>) {
+    let faction_exists = |id: &str| factions.iter().any(|(_, faction)| faction.id == id);
+
+    let mut valid_factions = Vec::new();
+
+    for (_, faction) in factions.iter() {
+        let problems = faction.problems(faction_exists);
+
+        if problems.is_empty() {
+            valid_factions.push(faction.clone());
+        } else {
+            println!(
+                "skipping faction \"{}\": {}",
+                faction.id,
+                problems.join(", ")
+            );
+        }
+    }
+
+    valid_factions.sort_by(|a, b| a.id.cmp(&b.id));
+
+    // Without it the player has no collision group, and nothing could ever hurt them
+    let has_player_faction = valid_factions
+        .iter()
+        .any(|faction| faction.id == PLAYER_FACTION);
+    if !has_player_faction {
+        println!("there is no valid \"{PLAYER_FACTION}\" faction, using one without relations");
+    }
+
+    // There are only so many collision groups to go around, and the implicit player faction
+    // needs one of them
+    let max_factions = if has_player_faction {
+        MAX_FACTIONS
+    } else {
+        MAX_FACTIONS - 1
+    };
+    for faction in valid_factions.iter().skip(max_factions) {
+        println!(
+            "skipping faction \"{}\": there are too many factions",
+            faction.id
+        );
+    }
+    valid_factions.truncate(max_factions);
+
+    if !has_player_faction {
+        valid_factions.push(FactionData {
+            id: PLAYER_FACTION.to_string(),
+            relations: Vec::new(),
+        });
+    }
+
+    commands.insert_resource(FactionRegistry {
+        factions: valid_factions,
+    });
+}
+
+/// Puts characters into the collision group of their faction.
+pub fn assign_faction_groups(
+    mut faction_query: Query<(&Faction, &mut CollisionGroups), Changed<Faction>>,
+    faction_registry: Res<FactionRegistry>,
+) {
+    for (faction, mut collision_groups) in faction_query.iter_mut() {
+        let memberships = collision_groups.memberships.bits() & !FACTION_GROUPS;
+
+        collision_groups.memberships =
+            Group::from_bits_truncate(memberships | faction_registry.group(&faction.0));
+    }
+}
@@ -2,6 +2,8 @@ use bevy::prelude::*;
 
 use crate::character::Character;
 
+use super::status::StatusEffects;
+
 pub struct HealthbarPlugin;
 
 impl Plugin for HealthbarPlugin {
@@ -23,6 +25,8 @@ const HEALTHBAR_HEIGHT: f32 = 8.0;
 
 const CONTAINER_OFFSET: f32 = 6.0;
 
+const HEALTHBAR_COLOR: Color = Color::DARK_GREEN;
+
 pub fn spawn_healthbar(commands: &mut Commands, offset: Vec2, tracked_entity: Entity) {
     let container = commands
         .spawn(SpriteBundle {
@@ -42,7 +46,7 @@ pub fn spawn_healthbar(commands: &mut Commands, offset: Vec2, tracked_entity: En
     commands
         .spawn(SpriteBundle {
             sprite: Sprite {
-                color: Color::DARK_GREEN,
+                color: HEALTHBAR_COLOR,
                 custom_size: Some(Vec2::new(
                     HEALTHBAR_WIDTH,
                     HEALTHBAR_HEIGHT + CONTAINER_OFFSET,
@@ -63,15 +67,21 @@ pub fn spawn_healthbar(commands: &mut Commands, offset: Vec2, tracked_entity: En
 fn handle_healthbars(
     mut commands: Commands,
     mut healthbars: Query<(Entity, &HealthBar, &mut Sprite, &mut Transform)>,
-    characters: Query<(&Character, &Transform), Without<HealthBar>>,
+    characters: Query<(&Character, &Transform, Option<&StatusEffects>), Without<HealthBar>>,
 ) {
     for (bar_entity, healthbar, mut sprite, mut healthbar_transform) in healthbars.iter_mut() {
-        if let Ok((character, character_transform)) = characters.get(healthbar.target) {
+        if let Ok((character, character_transform, status_effects)) =
+            characters.get(healthbar.target)
+        {
             healthbar_transform.translation =
                 character_transform.translation + healthbar.offset_position.extend(0.0);
             // TODO: Figure out why the healthbar isn't fully empty when at 0 health
             let percentage = character.health / character.max_health;
             sprite.custom_size = Some(Vec2::new(HEALTHBAR_WIDTH * percentage, HEALTHBAR_HEIGHT));
+            // The bar takes the color of the effect the character suffers from
+            sprite.color = status_effects
+                .and_then(|status_effects| status_effects.tint())
+                .unwrap_or(HEALTHBAR_COLOR);
         } else {
             // If the components cannot get fetched from the query, this means that the tracked
             // entity is either dead or shouldn't have the healthbar in the first place. Either
@@ -0,0 +1,201 @@
+use std::f32::consts::PI;
+
+use bevy::{prelude::*, sprite::Anchor};
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+
+use crate::{asset::GameSprites, character::ProjectileShooter};
+
+use super::{status::StatusEffects, Projectile, PROJECTILE_GROUP};
+
+/// The amount of points the edge of a swing's hitbox is made of.
+const ARC_SEGMENTS: usize = 8;
+
+/// A swing that hits everything in an arc in front of the attacker instead of firing projectiles.
+/// The damage, knockback and effects of the swing come from the projectile stats it's declared in.
+#[derive(Reflect, Clone, Debug, Deserialize)]
+pub struct MeleeData {
+    /// How far the swing reaches from the attacker's center.
+    pub range: f32,
+    /// How wide the swing is, in degrees.
+    pub arc: f32,
+    /// The time between starting the attack and the swing coming out, in seconds.
+    pub windup: f32,
+    /// How long the swing hits things, in seconds.
+    pub active: f32,
+    /// The time after the swing before the attacker can attack again, in seconds.
+    pub recovery: f32,
+}
+
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwingPhase {
+    Windup,
+    Active,
+    Recovery,
+}
+
+/// A swing that's in progress. Attackers can't start another attack until it's over.
+#[derive(Reflect, Component, Clone)]
+pub struct Swing {
+    pub phase: SwingPhase,
+    pub timer: Timer,
+    pub direction: Vec2,
+    pub target_group: u32,
+    /// The hitbox of the swing while it's active.
+    pub hitbox: Option<Entity>,
+}
+
+impl Swing {
+    pub fn new(melee: &MeleeData, direction: Vec2, target_group: u32) -> Self {
+        Self {
+            phase: SwingPhase::Windup,
+            timer: Timer::from_seconds(melee.windup, TimerMode::Once),
+            direction,
+            target_group,
+            hitbox: None,
+        }
+    }
+}
+
+/// Marks projectiles that are the hitbox of a swing. They stay in place and are never vanished by
+/// what they hit.
+#[derive(Component)]
+pub struct MeleeHitbox;
+
+/// The outline of a swing's hitbox, pointing up and starting at the attacker's center.
+fn arc_points(melee: &MeleeData) -> Vec<Vec2> {
+    let arc = melee.arc.to_radians().clamp(0.0, 2.0 * PI);
+
+    let mut points = vec![Vec2::ZERO];
+    points.extend((0..=ARC_SEGMENTS).map(|segment| {
+        let angle = -arc / 2.0 + arc * segment as f32 / ARC_SEGMENTS as f32;
+        Vec2::from_angle(angle).rotate(Vec2::Y) * melee.range
+    }));
+
+    points
+}
+
+fn spawn_hitbox(
+    commands: &mut Commands,
+    game_sprites: &mut ResMut<GameSprites>,
+    asset_server: &Res<AssetServer>,
+    attacker: Entity,
+    shooter: &ProjectileShooter,
+    melee: &MeleeData,
+    swing: &Swing,
+) -> Option<Entity> {
+    let collider = Collider::convex_hull(&arc_points(melee))?;
+    let half_arc = (melee.arc.to_radians() / 2.0).min(PI / 2.0);
+
+    let hitbox = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(
+                    2.0 * melee.range * half_arc.sin().max(0.25),
+                    melee.range,
+                )),
+                color: shooter.projectile_stats.color,
+                anchor: Anchor::BottomCenter,
+                ..Default::default()
+            },
+            transform: Transform {
+                translation: Vec3::Z,
+                rotation: Quat::from_rotation_arc_2d(Vec2::Y, swing.direction),
+                ..Default::default()
+            },
+            texture: game_sprites.get_or_load(&"slashNormal.png".to_string(), asset_server),
+            ..Default::default()
+        })
+        .insert(collider)
+        .insert(Sensor)
+        // The hitbox is attached to the attacker's body, but shouldn't make it any heavier
+        .insert(ColliderMassProperties::Density(0.0))
+        .insert(CollisionGroups::new(
+            Group::from_bits_truncate(PROJECTILE_GROUP),
+            Group::from_bits_truncate(swing.target_group | 0b0001),
+        ))
+        .insert(ActiveEvents::COLLISION_EVENTS)
+        .insert(Projectile {
+            owner: attacker,
+            stats: shooter.projectile_stats.clone(),
+            direction: swing.direction,
+            target_group: swing.target_group,
+            hits: Vec::new(),
+            bounces: 0,
+        })
+        .insert(MeleeHitbox)
+        .id();
+
+    commands.entity(attacker).add_child(hitbox);
+
+    Some(hitbox)
+}
+
+/// Moves swings through their phases, spawning the hitbox when the windup is over and removing it
+/// again once the swing stops being active.
+pub fn update_swings(
+    mut commands: Commands,
+    mut game_sprites: ResMut<GameSprites>,
+    asset_server: Res<AssetServer>,
+    mut swing_query: Query<(
+        Entity,
+        &mut Swing,
+        &ProjectileShooter,
+        Option<&StatusEffects>,
+    )>,
+    time: Res<Time>,
+) {
+    for (entity, mut swing, shooter, status_effects) in swing_query.iter_mut() {
+        // The attacker switched to a weapon that doesn't swing
+        let Some(melee) = &shooter.melee else {
+            if let Some(hitbox) = swing.hitbox.take() {
+                commands.entity(hitbox).despawn_recursive();
+            }
+            commands.entity(entity).remove::<Swing>();
+            continue;
+        };
+
+        // Getting stunned interrupts the swing
+        let stunned = status_effects.is_some_and(|status_effects| status_effects.is_stunned());
+
+        if stunned && swing.phase != SwingPhase::Recovery {
+            if let Some(hitbox) = swing.hitbox.take() {
+                commands.entity(hitbox).despawn_recursive();
+            }
+            swing.phase = SwingPhase::Recovery;
+            swing.timer = Timer::from_seconds(melee.recovery, TimerMode::Once);
+        }
+
+        swing.timer.tick(time.delta());
+
+        if !swing.timer.finished() {
+            continue;
+        }
+
+        match swing.phase {
+            SwingPhase::Windup => {
+                swing.hitbox = spawn_hitbox(
+                    &mut commands,
+                    &mut game_sprites,
+                    &asset_server,
+                    entity,
+                    shooter,
+                    melee,
+                    &swing,
+                );
+                swing.phase = SwingPhase::Active;
+                swing.timer = Timer::from_seconds(melee.active, TimerMode::Once);
+            }
+            SwingPhase::Active => {
+                if let Some(hitbox) = swing.hitbox.take() {
+                    commands.entity(hitbox).despawn_recursive();
+                }
+                swing.phase = SwingPhase::Recovery;
+                swing.timer = Timer::from_seconds(melee.recovery, TimerMode::Once);
+            }
+            SwingPhase::Recovery => {
+                commands.entity(entity).remove::<Swing>();
+            }
+        }
+    }
+}
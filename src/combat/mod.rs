@@ -17,9 +17,23 @@ use crate::{
     world::WorldObject,
 };
 
-use self::healthbar::HealthbarPlugin;
+use self::{
+    behavior::ProjectileBehavior,
+    defense::{DamageType, Defense},
+    faction::{Faction, FactionRegistry},
+    healthbar::HealthbarPlugin,
+    melee::{MeleeHitbox, Swing},
+    pattern::ProjectilePattern,
+    status::{StatusEffect, StatusEffects},
+};
 
+pub mod behavior;
+pub mod defense;
+pub mod faction;
 pub mod healthbar;
+pub mod melee;
+pub mod pattern;
+pub mod status;
 
 pub struct CombatPlugin;
 
@@ -32,6 +46,14 @@ impl Plugin for CombatPlugin {
         app.add_plugins(HealthbarPlugin)
             .add_event::<SpawnProjectileEvent>()
             .add_event::<CharacterAttackEvent>()
+            .add_systems(
+                OnEnter(GameState::PreparingWorld),
+                faction::prepare_faction_registry,
+            )
+            .add_systems(
+                Update,
+                faction::assign_faction_groups.run_if(resource_exists::<FactionRegistry>()),
+            )
             .add_systems(
                 Update,
                 (
@@ -39,6 +61,8 @@ impl Plugin for CombatPlugin {
                     character_attack_event,
                     collision_event,
                     immunity_update,
+                    status::update_status_effects,
+                    melee::update_swings,
                 )
                     .run_if(in_state(GameState::InGame)),
             )
@@ -47,8 +71,14 @@ impl Plugin for CombatPlugin {
                 handle_projectiles.run_if(in_state(GameState::InGame)),
             )
             .register_type::<Projectile>()
+            .register_type::<Defense>()
+            .register_type::<Faction>()
             .register_type::<Immunity>()
-            .register_type::<ProjectileStats>();
+            .register_type::<StatusEffects>()
+            .register_type::<ProjectileStats>()
+            .register_type::<ProjectilePattern>()
+            .register_type::<ProjectileBehavior>()
+            .register_type::<Swing>();
     }
 }
 
@@ -75,6 +105,13 @@ pub struct ProjectileStats {
     pub life_time: Timer,
     pub scale: f32,
     pub color: Color,
+    #[serde(default)]
+    pub damage_type: DamageType,
+    /// The effects applied to the characters that get hit.
+    #[serde(default)]
+    pub effects: Vec<StatusEffect>,
+    #[serde(default)]
+    pub behavior: ProjectileBehavior,
 }
 
 #[derive(Reflect, Component, Clone)]
@@ -82,11 +119,36 @@ pub struct Projectile {
     pub owner: Entity,
     pub stats: ProjectileStats,
     pub direction: Vec2,
+    /// The collision group of the characters the projectile is meant to hit.
+    pub target_group: u32,
+    /// The characters the projectile has hit so far. A piercing projectile hits each one once.
+    pub hits: Vec<Entity>,
+    pub bounces: u32,
 }
 
 #[derive(Reflect, Component)]
 pub struct Immunity(pub Timer);
 
+/// Returns both entities of a collision that just started, the one matching `is_first` first.
+/// Rapier reports the entities of a collision in any order. Returns `None` for collisions that
+/// stopped and for collisions where neither entity matches.
+pub fn started_collision(
+    event: &CollisionEvent,
+    is_first: impl Fn(Entity) -> bool,
+) -> Option<(Entity, Entity)> {
+    let CollisionEvent::Started(first, second, _) = event else {
+        return None;
+    };
+
+    if is_first(*first) {
+        Some((*first, *second))
+    } else if is_first(*second) {
+        Some((*second, *first))
+    } else {
+        None
+    }
+}
+
 fn spawn_projectile(
     commands: &mut Commands,
     game_sprites: &mut ResMut<GameSprites>,
@@ -135,11 +197,15 @@ fn spawn_projectile(
             owner,
             stats: projectile_stats,
             direction,
+            target_group,
+            hits: Vec::new(),
+            bounces: 0,
         });
 }
 
 fn handle_projectiles(
     mut projectile_query: Query<(Entity, &mut Projectile, &mut Transform, &mut Velocity)>,
+    target_query: Query<(&Transform, &CollisionGroups), (With<Character>, Without<Projectile>)>,
     mut vanish_writer: EventWriter<VanishEvent>,
     time: Res<Time>,
 ) {
@@ -159,6 +225,21 @@ fn handle_projectiles(
             vanish_writer.send(VanishEvent { entity });
         }
 
+        if projectile.stats.behavior.homing > 0.0 {
+            let target_group = Group::from_bits_truncate(projectile.target_group);
+            let targets = target_query
+                .iter()
+                .filter(|(_, groups)| groups.memberships.intersects(target_group))
+                .map(|(target_transform, _)| target_transform.translation.truncate());
+
+            projectile.direction = behavior::home_in(
+                transform.translation.truncate(),
+                projectile.direction,
+                projectile.stats.behavior.homing.to_radians() * delta,
+                targets,
+            );
+        }
+
         transform.rotation = Quat::from_rotation_arc_2d(Vec2::Y, projectile.direction);
 
         velocity.linvel = projectile.direction * projectile.stats.speed * delta;
@@ -166,43 +247,81 @@ fn handle_projectiles(
 }
 
 fn collision_event(
-    projectile_query: Query<&Projectile>,
+    mut projectile_query: Query<(&mut Projectile, &Transform, Option<&MeleeHitbox>)>,
     character_query: Query<&Character>,
+    collider_query: Query<(&Collider, &GlobalTransform), Without<Sensor>>,
     mut collision_events: EventReader<CollisionEvent>,
     mut attack_event_writer: EventWriter<CharacterAttackEvent>,
     mut projectile_vanish_writer: EventWriter<VanishEvent>,
 ) {
     for event in collision_events.read() {
-        if let CollisionEvent::Started(first, second, _) = event {
-            // We check each side two times because both entities may not be always in the same order
-            // TODO: Follow the DRY principle
-            if let Ok(hit_projectile) = projectile_query.get(*first) {
-                if character_query.get(*second).is_ok() {
-                    attack_event_writer.send(CharacterAttackEvent {
-                        victim: *second,
-                        projectile: (*hit_projectile).clone(),
-                    });
-                }
-                projectile_vanish_writer.send(VanishEvent { entity: *first });
-                continue; // If this branch happened, there's no reason to check a second time, so we carry on
+        let Some((projectile_entity, other)) =
+            started_collision(event, |entity| projectile_query.contains(entity))
+        else {
+            continue;
+        };
+
+        let Ok((mut projectile, transform, melee_hitbox)) =
+            projectile_query.get_mut(projectile_entity)
+        else {
+            continue;
+        };
+
+        // Swings hit every target they touch once and aren't stopped by anything
+        let is_melee = melee_hitbox.is_some();
+
+        if character_query.contains(other) {
+            if projectile.hits.contains(&other) {
+                continue;
             }
 
-            if let Ok(hit_projectile) = projectile_query.get(*second) {
-                if character_query.get(*first).is_ok() {
-                    attack_event_writer.send(CharacterAttackEvent {
-                        victim: *first,
-                        projectile: (*hit_projectile).clone(),
-                    });
-                }
-                projectile_vanish_writer.send(VanishEvent { entity: *second });
+            attack_event_writer.send(CharacterAttackEvent {
+                victim: other,
+                projectile: projectile.clone(),
+            });
+            projectile.hits.push(other);
+
+            if !is_melee && projectile.hits.len() as u32 > projectile.stats.behavior.pierce {
+                projectile_vanish_writer.send(VanishEvent {
+                    entity: projectile_entity,
+                });
             }
+
+            continue;
+        }
+
+        if is_melee {
+            continue;
         }
+
+        if projectile.bounces < projectile.stats.behavior.bounces {
+            if let Ok((collider, collider_transform)) = collider_query.get(other) {
+                projectile.direction = behavior::bounce(
+                    projectile.direction,
+                    transform.translation.truncate(),
+                    collider,
+                    collider_transform,
+                );
+                projectile.bounces += 1;
+                continue;
+            }
+        }
+
+        projectile_vanish_writer.send(VanishEvent {
+            entity: projectile_entity,
+        });
     }
 }
 
 fn character_attack_event(
     mut attack_events: EventReader<CharacterAttackEvent>,
-    mut character_query: Query<(&mut Character, &mut Immunity, &mut Velocity)>,
+    mut character_query: Query<(
+        &mut Character,
+        &mut Immunity,
+        &mut Velocity,
+        Option<&Defense>,
+        Option<&mut StatusEffects>,
+    )>,
     mut npc_query: Query<&mut NpcController>,
     mut regen_query: Query<&mut HealthRegen>,
     mut hit_flash_writer: EventWriter<HitFlashEvent>,
@@ -211,17 +330,37 @@ fn character_attack_event(
     let delta = time.delta_seconds();
 
     for event in attack_events.read() {
-        if let Ok((mut character, mut immunity, mut velocity)) =
+        if let Ok((mut character, mut immunity, mut velocity, defense, status_effects)) =
             character_query.get_mut(event.victim)
         {
             if immunity.0.finished() {
-                character.health -= event.projectile.stats.damage;
+                let stats = &event.projectile.stats;
 
-                velocity.linvel +=
-                    event.projectile.direction * (event.projectile.stats.knockback * delta);
+                character.health -= match defense {
+                    Some(defense) => defense.mitigate(stats.damage, stats.damage_type),
+                    None => stats.damage,
+                };
+
+                let knockback_multiplier = status_effects
+                    .as_ref()
+                    .map_or(1.0, |status_effects| status_effects.knockback_multiplier());
+
+                velocity.linvel += event.projectile.direction
+                    * (event.projectile.stats.knockback * knockback_multiplier * delta);
+
+                if let Some(mut status_effects) = status_effects {
+                    for effect in stats.effects.iter() {
+                        status_effects.apply(effect);
+                    }
+                }
 
                 if let Ok(mut npc) = npc_query.get_mut(event.victim) {
-                    npc.target = Some(NpcTarget::Character(event.projectile.owner));
+                    // Fleeing NPCs keep running, but from whoever hurt them last
+                    npc.target = match npc.target {
+                        Some(NpcTarget::Flee(_)) => Some(NpcTarget::Flee(event.projectile.owner)),
+                        _ => Some(NpcTarget::Character(event.projectile.owner)),
+                    };
+                    npc.attacker = Some(event.projectile.owner);
                 }
 
                 if let Ok(mut health_regen) = regen_query.get_mut(event.victim) {
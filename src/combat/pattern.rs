@@ -0,0 +1,68 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use serde::Deserialize;
+
+/// How a shooter fires its projectiles. The default fires a single, perfectly aimed projectile
+/// per attack.
+#[derive(Reflect, Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ProjectilePattern {
+    /// How many projectiles are fired at once, fanned out evenly over the spread.
+    pub count: u32,
+    /// The angle between the outermost projectiles, in degrees.
+    pub spread: f32,
+    /// How many times the projectiles are fired per attack.
+    pub burst: u32,
+    /// The time between the shots of a burst, in seconds.
+    pub burst_delay: f32,
+    /// The largest angle a shot can stray from where it's aimed, in degrees.
+    pub inaccuracy: f32,
+}
+
+impl Default for ProjectilePattern {
+    fn default() -> Self {
+        Self {
+            count: 1,
+            spread: 0.0,
+            burst: 1,
+            burst_delay: 0.1,
+            inaccuracy: 0.0,
+        }
+    }
+}
+
+impl ProjectilePattern {
+    /// The directions of the projectiles of a single shot aimed in the given direction.
+    pub fn directions(&self, aim: Vec2, rng: &mut impl Rng) -> Vec<Vec2> {
+        let count = self.count.max(1);
+        let spread = self.spread.to_radians();
+        let inaccuracy = self.inaccuracy.to_radians().abs();
+
+        // The whole shot strays by the same angle, so a spread keeps its shape
+        let aim = if inaccuracy > 0.0 {
+            Vec2::from_angle(rng.gen_range(-inaccuracy..=inaccuracy)).rotate(aim)
+        } else {
+            aim
+        };
+
+        (0..count)
+            .map(|index| {
+                let angle = match count {
+                    1 => 0.0,
+                    _ => -spread / 2.0 + spread * index as f32 / (count - 1) as f32,
+                };
+
+                Vec2::from_angle(angle).rotate(aim)
+            })
+            .collect()
+    }
+}
+
+/// The shots of a burst that haven't been fired yet.
+#[derive(Reflect, Clone, Debug)]
+pub struct Burst {
+    pub shots_left: u32,
+    pub delay: Timer,
+    pub target: Vec2,
+    pub target_group: u32,
+}
@@ -0,0 +1,194 @@
+//! Timed effects that projectiles leave on the characters they hit.
+//!
+//! Hitting a character that already suffers from the same kind of effect follows the rules of
+//! [`StatusKind::stacking`]: burning, slows and knockback resistance are refreshed, poison stacks
+//! and a stun has to wear off before the character can be stunned again.
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::character::Character;
+
+use super::defense::{DamageType, Defense};
+
+/// How many times poison can stack on a single character.
+const MAX_POISON_STACKS: u32 = 5;
+
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum StatusKind {
+    /// Deals fire damage every second, reduced by the victim's [`Defense`].
+    Burning { damage: f32 },
+    /// Deals damage every second for every stack. Armor doesn't help against it.
+    Poison { damage: f32 },
+    /// Reduces the movement speed by the given fraction.
+    Slow { amount: f32 },
+    /// Keeps the character from moving and attacking.
+    Stun,
+    /// Reduces the knockback taken by the given fraction.
+    KnockbackResistance { amount: f32 },
+}
+
+/// What happens when an effect hits a character that already suffers from the same kind.
+pub enum Stacking {
+    /// The stronger of both effects is kept and the duration starts over.
+    Refresh,
+    /// Another stack is added, up to the given amount, and the duration starts over.
+    Stack(u32),
+    /// The new effect is ignored.
+    Keep,
+}
+
+impl StatusKind {
+    pub fn stacking(&self) -> Stacking {
+        match self {
+            StatusKind::Poison { .. } => Stacking::Stack(MAX_POISON_STACKS),
+            // Stuns that could be refreshed would keep characters stunned forever
+            StatusKind::Stun => Stacking::Keep,
+            _ => Stacking::Refresh,
+        }
+    }
+
+    /// Whether both effects are of the same kind, regardless of their strength.
+    pub fn same_kind(&self, other: &StatusKind) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    /// How strong the effect is, for comparing effects of the same kind.
+    fn strength(&self) -> f32 {
+        match self {
+            StatusKind::Burning { damage } | StatusKind::Poison { damage } => *damage,
+            StatusKind::Slow { amount } | StatusKind::KnockbackResistance { amount } => *amount,
+            StatusKind::Stun => 1.0,
+        }
+    }
+
+    /// The color the healthbar of an affected character is tinted with.
+    pub fn color(&self) -> Color {
+        match self {
+            StatusKind::Burning { .. } => Color::rgb(0.9, 0.4, 0.1),
+            StatusKind::Poison { .. } => Color::rgb(0.5, 0.2, 0.6),
+            StatusKind::Slow { .. } => Color::rgb(0.4, 0.7, 0.9),
+            StatusKind::Stun => Color::rgb(0.9, 0.85, 0.2),
+            StatusKind::KnockbackResistance { .. } => Color::rgb(0.55, 0.55, 0.6),
+        }
+    }
+}
+
+/// An effect as it's declared in a projectile.
+#[derive(Reflect, Clone, Debug, Deserialize)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    /// How long the effect lasts, in seconds.
+    pub duration: f32,
+}
+
+#[derive(Reflect, Clone, Debug)]
+pub struct ActiveEffect {
+    pub kind: StatusKind,
+    pub stacks: u32,
+    /// The time until the effect wears off, in seconds.
+    pub remaining: f32,
+}
+
+/// The effects a character currently suffers from. Characters without it are immune to them.
+#[derive(Reflect, Component, Clone, Default)]
+pub struct StatusEffects {
+    pub active: Vec<ActiveEffect>,
+}
+
+impl StatusEffects {
+    pub fn apply(&mut self, effect: &StatusEffect) {
+        let Some(active) = self
+            .active
+            .iter_mut()
+            .find(|active| active.kind.same_kind(&effect.kind))
+        else {
+            self.active.push(ActiveEffect {
+                kind: effect.kind,
+                stacks: 1,
+                remaining: effect.duration,
+            });
+            return;
+        };
+
+        match effect.kind.stacking() {
+            Stacking::Refresh => {
+                if effect.kind.strength() >= active.kind.strength() {
+                    active.kind = effect.kind;
+                }
+
+                active.remaining = active.remaining.max(effect.duration);
+            }
+            Stacking::Stack(max_stacks) => {
+                active.stacks = (active.stacks + 1).min(max_stacks);
+                active.remaining = active.remaining.max(effect.duration);
+            }
+            Stacking::Keep => {}
+        }
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.active
+            .iter()
+            .any(|active| active.kind == StatusKind::Stun)
+    }
+
+    /// The multiplier applied to the character's speed.
+    pub fn speed_multiplier(&self) -> f32 {
+        self.active
+            .iter()
+            .map(|active| match active.kind {
+                StatusKind::Slow { amount } => 1.0 - amount.clamp(0.0, 1.0),
+                _ => 1.0,
+            })
+            .product()
+    }
+
+    /// The multiplier applied to the knockback the character takes.
+    pub fn knockback_multiplier(&self) -> f32 {
+        self.active
+            .iter()
+            .map(|active| match active.kind {
+                StatusKind::KnockbackResistance { amount } => 1.0 - amount.clamp(0.0, 1.0),
+                _ => 1.0,
+            })
+            .product()
+    }
+
+    /// The color of the most recent effect, if there is any.
+    pub fn tint(&self) -> Option<Color> {
+        self.active.last().map(|active| active.kind.color())
+    }
+}
+
+/// Deals the damage over time and removes the effects that have worn off.
+pub fn update_status_effects(
+    mut status_query: Query<(&mut StatusEffects, &mut Character, Option<&Defense>)>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+
+    for (mut status_effects, mut character, defense) in status_query.iter_mut() {
+        if status_effects.active.is_empty() {
+            continue;
+        }
+
+        for active in status_effects.active.iter_mut() {
+            let damage = match active.kind {
+                StatusKind::Burning { damage } => {
+                    let damage = damage * delta;
+                    defense.map_or(damage, |defense| defense.mitigate(damage, DamageType::Fire))
+                }
+                StatusKind::Poison { damage } => damage * active.stacks as f32 * delta,
+                _ => 0.0,
+            };
+
+            character.health -= damage;
+            active.remaining -= delta;
+        }
+
+        status_effects
+            .active
+            .retain(|active| active.remaining > 0.0);
+    }
+}
@@ -0,0 +1,212 @@
+use bevy::{prelude::*, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset::{item::ItemData, recipe::RecipeData, GameSprites},
+    inventory::{pickup, Inventory, ItemRegistry},
+    state::GameState,
+};
+
+pub struct CraftingPlugin;
+
+/// How close the player has to stand to a workstation to use it.
+pub const WORKSTATION_RANGE: f32 = 200.0;
+
+impl Plugin for CraftingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CraftEvent>()
+            .add_systems(OnEnter(GameState::PreparingWorld), prepare_recipe_book)
+            .add_systems(
+                Update,
+                (craft_event, crafting_progress).run_if(in_state(GameState::InGame)),
+            )
+            .register_type::<Workstation>();
+    }
+}
+
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WorkstationKind {
+    Campfire,
+    Workbench,
+}
+
+/// Marks an entity that allows crafting the recipes that require it.
+#[derive(Reflect, Component, Clone, Copy)]
+pub struct Workstation(pub WorkstationKind);
+
+/// Every valid recipe, sorted by id.
+#[derive(Resource, Default)]
+pub struct RecipeBook {
+    recipes: Vec<RecipeData>,
+}
+
+impl RecipeBook {
+    pub fn get(&self, id: &str) -> Option<&RecipeData> {
+        self.recipes.iter().find(|recipe| recipe.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RecipeData> {
+        self.recipes.iter()
+    }
+}
+
+/// A recipe that's being crafted. Its inputs have already been taken out of the inventory.
+#[derive(Component)]
+pub struct Crafting {
+    pub recipe: String,
+    pub timer: Timer,
+}
+
+/// Requests the entity to start crafting the recipe with the given id.
+#[derive(Event)]
+pub struct CraftEvent {
+    pub entity: Entity,
+    pub recipe: String,
+}
+
+/// Checks whether the inventory holds every input of the recipe.
+pub fn has_inputs(inventory: &Inventory, recipe: &RecipeData) -> bool {
+    recipe
+        .inputs
+        .iter()
+        .all(|(item, amount)| inventory.count(item) >= *amount)
+}
+
+/// Checks whether a workstation of the required kind is in range of the position.
+pub fn workstation_in_range(
+    recipe: &RecipeData,
+    position: Vec2,
+    mut workstations: impl Iterator<Item = (Vec2, WorkstationKind)>,
+) -> bool {
+    let Some(required) = recipe.workstation else {
+        return true;
+    };
+
+    workstations.any(|(workstation_position, kind)| {
+        kind == required && workstation_position.distance(position) <= WORKSTATION_RANGE
+    })
+}
+
+/// Builds the recipe book, reporting and leaving out every recipe that has problems.
+fn prepare_recipe_book(
+    mut commands: Commands,
+    recipes: Res<Assets<RecipeData>>,
+    items: Res<Assets<ItemData>>,
+) {
+    let item_exists = |id: &str| items.iter().any(|(_, item)| item.id == id);
+
+    let mut valid_recipes = Vec::new();
+
+    for (_, recipe) in recipes.iter() {
+        let problems = recipe.problems(item_exists);
+
+        if problems.is_empty() {
+            valid_recipes.push(recipe.clone());
+        } else {
+            println!("skipping recipe \"{}\": {}", recipe.id, problems.join(", "));
+        }
+    }
+
+    valid_recipes.sort_by(|a, b| a.id.cmp(&b.id));
+
+    commands.insert_resource(RecipeBook {
+        recipes: valid_recipes,
+    });
+}
+
+fn craft_event(
+    mut commands: Commands,
+    mut craft_events: EventReader<CraftEvent>,
+    mut crafter_query: Query<(&mut Inventory, &Transform), Without<Crafting>>,
+    workstation_query: Query<(&Workstation, &GlobalTransform)>,
+    recipe_book: Res<RecipeBook>,
+) {
+    // The query can't see the crafts inserted by this system until the commands are applied, so
+    // a second event for the same entity in the same frame would use up the inputs twice
+    let mut started = HashSet::new();
+
+    for event in craft_events.read() {
+        let Some(recipe) = recipe_book.get(&event.recipe) else {
+            println!("attempted to craft an unknown recipe \"{}\"", event.recipe);
+            continue;
+        };
+
+        if started.contains(&event.entity) {
+            continue;
+        }
+
+        // Entities that are already crafting something are left out by the query
+        let Ok((mut inventory, transform)) = crafter_query.get_mut(event.entity) else {
+            continue;
+        };
+
+        let workstations = workstation_query
+            .iter()
+            .map(|(workstation, transform)| (transform.translation().truncate(), workstation.0));
+
+        if !workstation_in_range(recipe, transform.translation.truncate(), workstations) {
+            // Only recipes that require a workstation can be out of range of one
+            if let Some(workstation) = recipe.workstation {
+                println!("crafting {} requires a {workstation:?}", recipe.name);
+            }
+            continue;
+        }
+
+        if !has_inputs(&inventory, recipe) {
+            continue;
+        }
+
+        for (item, amount) in recipe.inputs.iter() {
+            inventory.remove(item, *amount);
+        }
+
+        started.insert(event.entity);
+        commands.entity(event.entity).insert(Crafting {
+            recipe: recipe.id.clone(),
+            timer: Timer::from_seconds(recipe.craft_time, TimerMode::Once),
+        });
+    }
+}
+
+/// Puts the outputs of finished recipes into the inventory. Whatever doesn't fit is dropped at the
+/// crafter's feet, since the inputs are already gone.
+fn crafting_progress(
+    mut commands: Commands,
+    mut game_sprites: ResMut<GameSprites>,
+    asset_server: Res<AssetServer>,
+    mut crafter_query: Query<(Entity, &mut Crafting, &mut Inventory, &Transform)>,
+    recipe_book: Res<RecipeBook>,
+    item_registry: Res<ItemRegistry>,
+    time: Res<Time>,
+) {
+    for (entity, mut crafting, mut inventory, transform) in crafter_query.iter_mut() {
+        crafting.timer.tick(time.delta());
+
+        if !crafting.timer.finished() {
+            continue;
+        }
+
+        if let Some(recipe) = recipe_book.get(&crafting.recipe) {
+            for (item_id, amount) in recipe.outputs.iter() {
+                let Some(item) = item_registry.get(item_id) else {
+                    continue;
+                };
+
+                let leftover = inventory.add(item_id, *amount, item.max_stack);
+
+                if leftover > 0 {
+                    pickup::spawn_pickup(
+                        &mut commands,
+                        &mut game_sprites,
+                        &asset_server,
+                        item,
+                        leftover,
+                        transform.translation.truncate(),
+                    );
+                }
+            }
+        }
+
+        commands.entity(entity).remove::<Crafting>();
+    }
+}
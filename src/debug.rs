@@ -3,6 +3,8 @@ use bevy_egui::{egui, EguiContexts};
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_rapier2d::prelude::*;
 
+use crate::{character::player::Player, world::biome::BiomeMap};
+
 pub struct DebugPlugin;
 
 impl Plugin for DebugPlugin {
@@ -17,8 +19,25 @@ impl Plugin for DebugPlugin {
     }
 }
 
-fn debug_render_toggle(mut contexts: EguiContexts, mut render_context: ResMut<DebugRenderContext>) {
+fn debug_render_toggle(
+    mut contexts: EguiContexts,
+    mut render_context: ResMut<DebugRenderContext>,
+    biome_map: Option<Res<BiomeMap>>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let biome = biome_map
+        .zip(player_query.get_single().ok())
+        .and_then(|(biome_map, transform)| {
+            biome_map
+                .biome_at(transform.translation.truncate())
+                .map(|biome| biome.name.clone())
+        });
+
     egui::Window::new("Debug Render").show(contexts.ctx_mut(), |ui| {
         ui.checkbox(&mut render_context.enabled, "Enable collider rendering");
+
+        if let Some(biome) = biome {
+            ui.label(format!("Biome: {biome}"));
+        }
     });
 }
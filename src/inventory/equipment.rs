@@ -0,0 +1,273 @@
+use bevy::{input::mouse::MouseWheel, prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset::item::WeaponData,
+    character::{player::Player, ProjectileShooter},
+    combat::{
+        behavior::ProjectileBehavior,
+        defense::{DamageType, Defense},
+        pattern::ProjectilePattern,
+        ProjectileStats,
+    },
+};
+
+use super::{Inventory, ItemRegistry};
+
+/// The amount of weapons that fit on the hotbar.
+pub const HOTBAR_SIZE: usize = 4;
+
+/// Switches to the next piece of armor in the inventory.
+const ARMOR_KEY: KeyCode = KeyCode::G;
+
+/// The keys selecting the hotbar slots, in order.
+const HOTBAR_KEYS: [KeyCode; HOTBAR_SIZE] =
+    [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4];
+
+/// The weapons an entity can switch between and the armor it wears. Every equipped item has to
+/// be in the entity's inventory, items that leave the inventory are unequipped again.
+#[derive(Reflect, Component, Clone, Serialize, Deserialize)]
+pub struct Equipment {
+    /// The item ids of the weapons on the hotbar.
+    pub hotbar: Vec<Option<String>>,
+    /// The hotbar slot of the weapon that's currently used.
+    pub selected: usize,
+    /// The item id of the armor that's worn.
+    #[serde(default)]
+    pub armor: Option<String>,
+}
+
+impl Default for Equipment {
+    fn default() -> Self {
+        Self {
+            hotbar: vec![None; HOTBAR_SIZE],
+            selected: 0,
+            armor: None,
+        }
+    }
+}
+
+impl Equipment {
+    /// The item id of the selected weapon. Nothing is equipped when the selected slot is empty.
+    pub fn equipped(&self) -> Option<&str> {
+        self.hotbar.get(self.selected)?.as_deref()
+    }
+}
+
+/// The attack used while no weapon is equipped.
+pub fn unarmed_shooter() -> ProjectileShooter {
+    ProjectileShooter {
+        projectile_stats: ProjectileStats {
+            damage: 2.0,
+            knockback: 5000.0,
+            speed: 25000.0,
+            life_time: Timer::from_seconds(0.15, TimerMode::Once),
+            scale: 0.75,
+            color: Color::WHITE,
+            damage_type: DamageType::Blunt,
+            effects: Vec::new(),
+            behavior: ProjectileBehavior::default(),
+        },
+        attack_speed: Timer::from_seconds(0.6, TimerMode::Once),
+        pattern: ProjectilePattern::default(),
+        melee: None,
+        burst: None,
+    }
+}
+
+fn weapon_shooter(weapon: &WeaponData) -> ProjectileShooter {
+    ProjectileShooter {
+        projectile_stats: weapon.projectile.stats(),
+        attack_speed: Timer::from_seconds(weapon.attack_speed, TimerMode::Once),
+        pattern: weapon.projectile.pattern.clone(),
+        melee: weapon.projectile.melee.clone(),
+        burst: None,
+    }
+}
+
+/// Keeps the equipment in line with the inventory: weapons that were picked up are put into free
+/// hotbar slots, armor is worn if nothing else is, and items that are gone are taken off.
+pub fn sync_equipment(
+    mut equipment_query: Query<(&mut Equipment, &Inventory), Changed<Inventory>>,
+    item_registry: Res<ItemRegistry>,
+) {
+    for (mut equipment, inventory) in equipment_query.iter_mut() {
+        let mut hotbar = equipment.hotbar.clone();
+
+        for slot in hotbar.iter_mut() {
+            if slot.as_ref().is_some_and(|item| inventory.count(item) == 0) {
+                *slot = None;
+            }
+        }
+
+        for stack in inventory.slots.iter().flatten() {
+            let is_weapon = item_registry
+                .get(&stack.item)
+                .is_some_and(|item| item.weapon.is_some());
+
+            if !is_weapon || hotbar.contains(&Some(stack.item.clone())) {
+                continue;
+            }
+
+            match hotbar.iter_mut().find(|slot| slot.is_none()) {
+                Some(slot) => *slot = Some(stack.item.clone()),
+                None => break,
+            }
+        }
+
+        let armor = equipment
+            .armor
+            .clone()
+            .filter(|item| inventory.count(item) > 0)
+            .or_else(|| armor_in(inventory, &item_registry).first().cloned());
+
+        // Only touching the equipment when something changes keeps the weapon from being
+        // readied again every time an item is picked up
+        if hotbar != equipment.hotbar {
+            equipment.hotbar = hotbar;
+        }
+
+        if armor != equipment.armor {
+            equipment.armor = armor;
+        }
+    }
+}
+
+/// Returns the ids of the armor items in the inventory, in the order of their slots.
+fn armor_in(inventory: &Inventory, item_registry: &ItemRegistry) -> Vec<String> {
+    let mut armor = Vec::new();
+
+    for stack in inventory.slots.iter().flatten() {
+        let is_armor = item_registry
+            .get(&stack.item)
+            .is_some_and(|item| item.armor.is_some());
+
+        if is_armor && !armor.contains(&stack.item) {
+            armor.push(stack.item.clone());
+        }
+    }
+
+    armor
+}
+
+/// Selects hotbar slots with the number keys and cycles through the weapons with the mouse wheel.
+pub fn hotbar_input(
+    mut player_query: Query<&mut Equipment, With<Player>>,
+    key_input: Res<Input<KeyCode>>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+) {
+    let Ok(mut equipment) = player_query.get_single_mut() else {
+        return;
+    };
+
+    for (slot, key) in HOTBAR_KEYS.iter().enumerate() {
+        if key_input.just_pressed(*key) && equipment.selected != slot {
+            equipment.selected = slot;
+        }
+    }
+
+    let scroll = mouse_wheel_events.read().map(|event| event.y).sum::<f32>();
+
+    if scroll == 0.0 {
+        return;
+    }
+
+    // Scrolling skips the empty slots, so it only switches between actual weapons
+    let size = equipment.hotbar.len();
+    let step = if scroll > 0.0 { size - 1 } else { 1 };
+    let mut slot = equipment.selected;
+
+    for _ in 0..size {
+        slot = (slot + step) % size;
+
+        if equipment.hotbar[slot].is_some() {
+            break;
+        }
+    }
+
+    if slot != equipment.selected && equipment.hotbar[slot].is_some() {
+        equipment.selected = slot;
+    }
+}
+
+/// Puts on the next piece of armor in the player's inventory.
+pub fn armor_input(
+    mut player_query: Query<(&mut Equipment, &Inventory), With<Player>>,
+    key_input: Res<Input<KeyCode>>,
+    item_registry: Res<ItemRegistry>,
+) {
+    if !key_input.just_pressed(ARMOR_KEY) {
+        return;
+    }
+
+    let Ok((mut equipment, inventory)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    let armor = armor_in(inventory, &item_registry);
+
+    let next = match &equipment.armor {
+        Some(current) => armor
+            .iter()
+            .skip_while(|item| *item != current)
+            .nth(1)
+            .or(armor.first()),
+        None => armor.first(),
+    };
+
+    if let Some(next) = next.cloned() {
+        if equipment.armor.as_ref() != Some(&next) {
+            equipment.armor = Some(next);
+        }
+    }
+}
+
+/// Swaps the attack of entities whenever they equip a different weapon.
+/// The weapon each entity last equipped is remembered, so that other changes to the equipment
+/// (e.g. putting on armor) don't reset the attack.
+pub fn equip_weapon(
+    mut equipment_query: Query<(Entity, &Equipment, &mut ProjectileShooter), Changed<Equipment>>,
+    item_registry: Res<ItemRegistry>,
+    mut equipped_weapons: Local<HashMap<Entity, Option<String>>>,
+    mut removed_equipment: RemovedComponents<Equipment>,
+) {
+    for entity in removed_equipment.read() {
+        equipped_weapons.remove(&entity);
+    }
+
+    for (entity, equipment, mut shooter) in equipment_query.iter_mut() {
+        let equipped = equipment.equipped().map(str::to_string);
+
+        if equipped_weapons.get(&entity) == Some(&equipped) {
+            continue;
+        }
+
+        let weapon = equipped
+            .as_deref()
+            .and_then(|item| item_registry.get(item))
+            .and_then(|item| item.weapon.as_ref());
+
+        // The new weapon has to be readied first, so that switching isn't faster than attacking
+        *shooter = match weapon {
+            Some(weapon) => weapon_shooter(weapon),
+            None => unarmed_shooter(),
+        };
+
+        equipped_weapons.insert(entity, equipped);
+    }
+}
+
+/// Replaces the defense of entities with the one of the armor they're wearing.
+pub fn equip_armor(
+    mut equipment_query: Query<(&Equipment, &mut Defense), Changed<Equipment>>,
+    item_registry: Res<ItemRegistry>,
+) {
+    for (equipment, mut defense) in equipment_query.iter_mut() {
+        *defense = equipment
+            .armor
+            .as_ref()
+            .and_then(|item| item_registry.get(item))
+            .and_then(|item| item.armor.clone())
+            .unwrap_or_default();
+    }
+}
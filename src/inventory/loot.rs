@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    asset::{npc::NpcId, GameSprites},
+    character::Character,
+    world::{seed::WorldRng, NpcPool},
+};
+
+use super::{pickup, ItemRegistry};
+
+/// How far from the NPC the dropped items are scattered.
+const DROP_SCATTER: f32 = 40.0;
+
+/// Describes what an NPC drops when it dies.
+/// Every roll picks a single entry, with the chance of each entry being its weight divided by the
+/// sum of all weights (including `nothing_weight`).
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct LootTable {
+    #[serde(default = "default_rolls")]
+    pub rolls: u32,
+    /// The weight of a roll not dropping anything.
+    #[serde(default)]
+    pub nothing_weight: u32,
+    pub entries: Vec<LootEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct LootEntry {
+    pub item: String,
+    pub weight: u32,
+    /// The minimum and maximum amount dropped, both inclusive.
+    pub amount: (u32, u32),
+}
+
+fn default_rolls() -> u32 {
+    1
+}
+
+impl LootTable {
+    /// Rolls the table, returning the items and amounts that should be dropped.
+    pub fn roll(&self, rng: &mut StdRng) -> Vec<(String, u32)> {
+        let mut drops = Vec::new();
+
+        let total_weight =
+            self.entries.iter().map(|entry| entry.weight).sum::<u32>() + self.nothing_weight;
+
+        if total_weight == 0 {
+            return drops;
+        }
+
+        for _ in 0..self.rolls {
+            let mut pick = rng.gen_range(0..total_weight);
+
+            for entry in self.entries.iter() {
+                if pick < entry.weight {
+                    let (min, max) = entry.amount;
+                    let amount = rng.gen_range(min..=max.max(min));
+
+                    if amount > 0 {
+                        drops.push((entry.item.clone(), amount));
+                    }
+                    break;
+                }
+
+                pick -= entry.weight;
+            }
+        }
+
+        drops
+    }
+
+    /// Reports entries that refer to items which don't exist, which usually means a typo.
+    pub fn validate(&self, owner: &str, item_registry: &ItemRegistry) {
+        for entry in self.entries.iter() {
+            if item_registry.get(&entry.item).is_none() {
+                println!(
+                    "loot table of \"{owner}\" refers to an unknown item \"{}\"",
+                    entry.item
+                );
+            }
+        }
+    }
+}
+
+/// Marks a dead NPC whose loot has already been dropped.
+/// Dead characters stay around until their vanishing animation is done, so without it the loot
+/// would be dropped every frame until then.
+#[derive(Component)]
+pub struct LootDropped;
+
+pub fn drop_loot(
+    mut commands: Commands,
+    mut game_sprites: ResMut<GameSprites>,
+    asset_server: Res<AssetServer>,
+    npc_pool: Res<NpcPool>,
+    item_registry: Res<ItemRegistry>,
+    mut world_rng: ResMut<WorldRng>,
+    npc_query: Query<(Entity, &Character, &NpcId, &Transform), Without<LootDropped>>,
+) {
+    for (entity, character, npc_id, transform) in npc_query.iter() {
+        if character.health > 0.0 {
+            continue;
+        }
+
+        commands.entity(entity).insert(LootDropped);
+
+        let Some(npc) = npc_pool.get(&npc_id.0) else {
+            continue;
+        };
+
+        for (item_id, amount) in npc.loot.roll(&mut world_rng.spawning) {
+            let Some(item) = item_registry.get(&item_id) else {
+                continue;
+            };
+
+            let offset = Vec2::new(
+                world_rng.spawning.gen_range(-DROP_SCATTER..DROP_SCATTER),
+                world_rng.spawning.gen_range(-DROP_SCATTER..DROP_SCATTER),
+            );
+
+            pickup::spawn_pickup(
+                &mut commands,
+                &mut game_sprites,
+                &asset_server,
+                item,
+                amount,
+                transform.translation.truncate() + offset,
+            );
+        }
+    }
+}
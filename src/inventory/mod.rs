@@ -0,0 +1,292 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset::{item::ItemData, npc::NpcData},
+    state::GameState,
+    world::prepare_world,
+};
+
+pub mod equipment;
+pub mod loot;
+pub mod pickup;
+
+pub struct InventoryPlugin;
+
+/// The amount of slots in the player's inventory.
+pub const PLAYER_INVENTORY_SIZE: usize = 20;
+
+impl Plugin for InventoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AddItemEvent>()
+            .add_event::<RemoveItemEvent>()
+            .add_event::<MoveItemEvent>()
+            .add_systems(
+                OnEnter(GameState::PreparingWorld),
+                prepare_item_registry.before(prepare_world),
+            )
+            .add_systems(
+                Update,
+                (
+                    add_item_event,
+                    remove_item_event,
+                    move_item_event,
+                    loot::drop_loot,
+                    pickup::collect_pickups,
+                    pickup::pickup_life_time,
+                    (
+                        equipment::sync_equipment,
+                        (equipment::hotbar_input, equipment::armor_input),
+                        (equipment::equip_weapon, equipment::equip_armor),
+                    )
+                        .chain(),
+                )
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .register_type::<Inventory>()
+            .register_type::<equipment::Equipment>()
+            .register_type::<ItemStack>();
+    }
+}
+
+/// Every item that has been loaded, by id.
+#[derive(Resource, Default)]
+pub struct ItemRegistry {
+    items: HashMap<String, ItemData>,
+}
+
+impl ItemRegistry {
+    pub fn get(&self, id: &str) -> Option<&ItemData> {
+        self.items.get(id)
+    }
+}
+
+/// A pile of the same item taking up a single inventory slot.
+#[derive(Reflect, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item: String,
+    pub amount: u32,
+}
+
+#[derive(Reflect, Component, Clone, Serialize, Deserialize)]
+pub struct Inventory {
+    pub slots: Vec<Option<ItemStack>>,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new(PLAYER_INVENTORY_SIZE)
+    }
+}
+
+impl Inventory {
+    pub fn new(size: usize) -> Self {
+        Self {
+            slots: vec![None; size],
+        }
+    }
+
+    /// Returns how many of the given item are in the inventory.
+    pub fn count(&self, item: &str) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.amount)
+            .sum()
+    }
+
+    /// Adds the items to the inventory, topping up existing stacks before filling empty slots.
+    /// Returns the amount that didn't fit.
+    pub fn add(&mut self, item: &str, mut amount: u32, max_stack: u32) -> u32 {
+        let max_stack = max_stack.max(1);
+
+        for stack in self.slots.iter_mut().flatten() {
+            if amount == 0 {
+                break;
+            }
+
+            if stack.item == item && stack.amount < max_stack {
+                let added = amount.min(max_stack - stack.amount);
+                stack.amount += added;
+                amount -= added;
+            }
+        }
+
+        for slot in self.slots.iter_mut() {
+            if amount == 0 {
+                break;
+            }
+
+            if slot.is_none() {
+                let added = amount.min(max_stack);
+                *slot = Some(ItemStack {
+                    item: item.to_string(),
+                    amount: added,
+                });
+                amount -= added;
+            }
+        }
+
+        amount
+    }
+
+    /// Removes the given amount of an item, taking from the last stacks first.
+    /// Nothing is removed if the inventory doesn't hold enough of the item.
+    pub fn remove(&mut self, item: &str, mut amount: u32) -> bool {
+        if self.count(item) < amount {
+            return false;
+        }
+
+        for slot in self.slots.iter_mut().rev() {
+            if amount == 0 {
+                break;
+            }
+
+            if let Some(stack) = slot.as_mut().filter(|stack| stack.item == item) {
+                let removed = amount.min(stack.amount);
+                stack.amount -= removed;
+                amount -= removed;
+
+                if stack.amount == 0 {
+                    *slot = None;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Moves the stack in one slot to another. Stacks of the same item are merged as far as
+    /// possible, different items swap places.
+    pub fn move_stack(&mut self, from: usize, to: usize, max_stack: u32) {
+        if from == to || from >= self.slots.len() || to >= self.slots.len() {
+            return;
+        }
+
+        let same_item = match (&self.slots[from], &self.slots[to]) {
+            (Some(source), Some(target)) => source.item == target.item,
+            _ => false,
+        };
+
+        if !same_item {
+            self.slots.swap(from, to);
+            return;
+        }
+
+        let target_amount = self.slots[to].as_ref().map_or(0, |stack| stack.amount);
+        let moved = self.slots[from]
+            .as_ref()
+            .map_or(0, |stack| stack.amount)
+            .min(max_stack.saturating_sub(target_amount));
+
+        if let Some(target) = self.slots[to].as_mut() {
+            target.amount += moved;
+        }
+
+        if let Some(source) = self.slots[from].as_mut() {
+            source.amount -= moved;
+
+            if source.amount == 0 {
+                self.slots[from] = None;
+            }
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct AddItemEvent {
+    pub entity: Entity,
+    pub item: String,
+    pub amount: u32,
+}
+
+#[derive(Event)]
+pub struct RemoveItemEvent {
+    pub entity: Entity,
+    pub item: String,
+    pub amount: u32,
+}
+
+#[derive(Event)]
+pub struct MoveItemEvent {
+    pub entity: Entity,
+    pub from: usize,
+    pub to: usize,
+}
+
+fn prepare_item_registry(
+    mut commands: Commands,
+    items: Res<Assets<ItemData>>,
+    npcs: Res<Assets<NpcData>>,
+) {
+    let item_registry = ItemRegistry {
+        items: items
+            .iter()
+            .map(|(_, item)| (item.id.clone(), item.clone()))
+            .collect(),
+    };
+
+    for (_, npc) in npcs.iter() {
+        npc.loot.validate(&npc.id, &item_registry);
+    }
+
+    commands.insert_resource(item_registry);
+}
+
+fn add_item_event(
+    mut inventory_query: Query<&mut Inventory>,
+    mut add_events: EventReader<AddItemEvent>,
+    item_registry: Res<ItemRegistry>,
+) {
+    for event in add_events.read() {
+        let Some(item) = item_registry.get(&event.item) else {
+            println!("attempted to add an unknown item \"{}\"", event.item);
+            continue;
+        };
+
+        if let Ok(mut inventory) = inventory_query.get_mut(event.entity) {
+            let leftover = inventory.add(&item.id, event.amount, item.max_stack);
+
+            if leftover > 0 {
+                println!("inventory is full, {leftover}x {} didn't fit", item.id);
+            }
+        }
+    }
+}
+
+fn remove_item_event(
+    mut inventory_query: Query<&mut Inventory>,
+    mut remove_events: EventReader<RemoveItemEvent>,
+) {
+    for event in remove_events.read() {
+        if let Ok(mut inventory) = inventory_query.get_mut(event.entity) {
+            if !inventory.remove(&event.item, event.amount) {
+                println!(
+                    "attempted to remove {}x {}, but the inventory doesn't hold enough",
+                    event.amount, event.item
+                );
+            }
+        }
+    }
+}
+
+fn move_item_event(
+    mut inventory_query: Query<&mut Inventory>,
+    mut move_events: EventReader<MoveItemEvent>,
+    item_registry: Res<ItemRegistry>,
+) {
+    for event in move_events.read() {
+        if let Ok(mut inventory) = inventory_query.get_mut(event.entity) {
+            let max_stack = inventory
+                .slots
+                .get(event.from)
+                .and_then(|slot| slot.as_ref())
+                .and_then(|stack| item_registry.get(&stack.item))
+                .map(|item| item.max_stack)
+                .unwrap_or(1);
+
+            inventory.move_stack(event.from, event.to, max_stack);
+        }
+    }
+}
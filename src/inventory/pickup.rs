@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    animation::{VanishEvent, WobbleBundle},
+    asset::{item::ItemData, GameSprites},
+    character::player::Player,
+    combat::{started_collision, PLAYER_GROUP},
+    world::WorldObject,
+};
+
+use super::{Inventory, ItemRegistry};
+
+pub const PICKUP_GROUP: u32 = 0b10000;
+
+/// How long a pickup stays in the world before it disappears, in seconds.
+const PICKUP_LIFE_TIME: f32 = 120.0;
+
+/// Items lying in the world, waiting to be collected by the player walking over them.
+#[derive(Component)]
+pub struct Pickup {
+    pub item: String,
+    pub amount: u32,
+    pub life_time: Timer,
+}
+
+pub fn spawn_pickup(
+    commands: &mut Commands,
+    game_sprites: &mut ResMut<GameSprites>,
+    asset_server: &Res<AssetServer>,
+    item: &ItemData,
+    amount: u32,
+    position: Vec2,
+) -> Entity {
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(48.0, 48.0)),
+                ..Default::default()
+            },
+            transform: Transform::from_translation(position.extend(-1.0)),
+            texture: game_sprites.get_or_load(&item.sprite_name, asset_server),
+            ..Default::default()
+        })
+        .insert(Collider::ball(24.0))
+        .insert(Sensor)
+        .insert(CollisionGroups::new(
+            Group::from_bits_truncate(PICKUP_GROUP),
+            Group::from_bits_truncate(PLAYER_GROUP),
+        ))
+        .insert(ActiveEvents::COLLISION_EVENTS)
+        .insert(WobbleBundle::new(Vec3::ONE))
+        .insert(WorldObject)
+        .insert(Name::new(format!("Pickup ({})", item.id)))
+        .insert(Pickup {
+            item: item.id.clone(),
+            amount,
+            life_time: Timer::from_seconds(PICKUP_LIFE_TIME, TimerMode::Once),
+        })
+        .id()
+}
+
+pub fn collect_pickups(
+    mut collision_events: EventReader<CollisionEvent>,
+    rapier_context: Res<RapierContext>,
+    mut pickup_query: Query<&mut Pickup>,
+    mut player_query: Query<(Entity, &mut Inventory), With<Player>>,
+    item_registry: Res<ItemRegistry>,
+    mut vanish_writer: EventWriter<VanishEvent>,
+) {
+    let Ok((player, mut inventory)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    let mut touched = collision_events
+        .read()
+        .filter_map(|event| started_collision(event, |entity| pickup_query.contains(entity)))
+        .filter(|(_, other)| *other == player)
+        .map(|(pickup_entity, _)| pickup_entity)
+        .collect::<Vec<_>>();
+
+    // Pickups that were left on the ground because the inventory was full get collected as soon
+    // as there's room again, without having to walk off and back onto them
+    if inventory.is_changed() {
+        touched.extend(
+            rapier_context
+                .intersections_with(player)
+                .filter(|(_, _, intersecting)| *intersecting)
+                .map(|(first, second, _)| if first == player { second } else { first }),
+        );
+    }
+
+    touched.sort();
+    touched.dedup();
+
+    for pickup_entity in touched {
+        let Ok(mut pickup) = pickup_query.get_mut(pickup_entity) else {
+            continue;
+        };
+
+        if pickup.amount == 0 {
+            continue;
+        }
+
+        let max_stack = item_registry
+            .get(&pickup.item)
+            .map(|item| item.max_stack)
+            .unwrap_or(1);
+
+        // Whatever doesn't fit into the inventory stays on the ground. The inventory only counts as
+        // changed when something fit, otherwise the pickup would be retried every frame.
+        let leftover =
+            inventory
+                .bypass_change_detection()
+                .add(&pickup.item, pickup.amount, max_stack);
+
+        if leftover != pickup.amount {
+            inventory.set_changed();
+            pickup.amount = leftover;
+        }
+
+        if pickup.amount == 0 {
+            vanish_writer.send(VanishEvent {
+                entity: pickup_entity,
+            });
+        }
+    }
+}
+
+pub fn pickup_life_time(
+    mut pickup_query: Query<(Entity, &mut Pickup)>,
+    mut vanish_writer: EventWriter<VanishEvent>,
+    time: Res<Time>,
+) {
+    for (entity, mut pickup) in pickup_query.iter_mut() {
+        pickup.life_time.tick(time.delta());
+
+        if pickup.life_time.just_finished() {
+            vanish_writer.send(VanishEvent { entity });
+        }
+    }
+}
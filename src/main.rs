@@ -4,9 +4,13 @@ use state::GameState;
 
 mod animation;
 mod asset;
+mod building;
 mod character;
 mod combat;
+mod crafting;
 mod debug;
+mod inventory;
+mod save;
 mod state;
 mod ui;
 mod world;
@@ -24,6 +28,10 @@ fn main() {
             asset::AssetPlugin,
             combat::CombatPlugin,
             animation::AnimationPlugin,
+            save::SavePlugin,
+            inventory::InventoryPlugin,
+            crafting::CraftingPlugin,
+            building::BuildingPlugin,
         ))
         .add_state::<GameState>()
         .add_systems(Startup, setup_camera)
@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+
+use crate::state::GameState;
+
+use super::{SaveGameEvent, SaveKind};
+
+pub struct AutosavePlugin;
+
+impl Plugin for AutosavePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<AutosaveSettings>()
+            .init_resource::<AutosaveSettings>()
+            .init_resource::<AutosaveTimer>()
+            .add_systems(OnEnter(GameState::PreparingWorld), reset_autosave_timer)
+            .add_systems(OnEnter(GameState::Paused), autosave_on_pause)
+            .add_systems(
+                Update,
+                autosave_interval.run_if(in_state(GameState::InGame)),
+            );
+    }
+}
+
+/// Controls how often the game saves itself and how many autosaves are kept.
+#[derive(Resource, Reflect, Clone)]
+#[reflect(Resource)]
+pub struct AutosaveSettings {
+    pub enabled: bool,
+    /// The time between two autosaves while in-game, in seconds.
+    pub interval: f32,
+    /// The amount of autosaves that are kept per slot before the oldest one gets replaced.
+    pub kept: usize,
+}
+
+impl Default for AutosaveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: 300.0,
+            kept: 3,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct AutosaveTimer(Timer);
+
+fn reset_autosave_timer(
+    autosave_settings: Res<AutosaveSettings>,
+    mut autosave_timer: ResMut<AutosaveTimer>,
+) {
+    autosave_timer.0 = Timer::from_seconds(autosave_settings.interval, TimerMode::Repeating);
+}
+
+fn autosave_interval(
+    time: Res<Time>,
+    autosave_settings: Res<AutosaveSettings>,
+    mut autosave_timer: ResMut<AutosaveTimer>,
+    mut save_event: EventWriter<SaveGameEvent>,
+) {
+    if !autosave_settings.enabled {
+        return;
+    }
+
+    // Picks up interval changes made while the game is running
+    let interval = std::time::Duration::from_secs_f32(autosave_settings.interval.max(1.0));
+    if autosave_timer.0.duration() != interval {
+        autosave_timer.0.set_duration(interval);
+    }
+
+    autosave_timer.0.tick(time.delta());
+
+    if autosave_timer.0.just_finished() {
+        save_event.send(SaveGameEvent(SaveKind::Autosave));
+    }
+}
+
+fn autosave_on_pause(
+    autosave_settings: Res<AutosaveSettings>,
+    mut save_event: EventWriter<SaveGameEvent>,
+) {
+    if autosave_settings.enabled {
+        save_event.send(SaveGameEvent(SaveKind::Autosave));
+    }
+}
@@ -0,0 +1,90 @@
+//! Upgrades saves written by older builds of the game.
+//!
+//! Whenever the save format changes (e.g. a saved component gains a field), the new field gets a
+//! `#[serde(default)]` so that older saves still deserialize, [`CURRENT_VERSION`] gets bumped and
+//! a migration step is appended to [`MIGRATIONS`] that fills in whatever the default can't.
+
+use crate::{character::player::STARTING_WEAPON, world::clock::WorldClock};
+
+use super::{slot::SaveFile, SaveError};
+
+/// The version of the save format written by this build.
+pub const CURRENT_VERSION: u32 = 9;
+
+/// Upgrades a save by a single version.
+type Migration = fn(&mut SaveFile);
+
+/// The migration at index N upgrades a save from version N to version N + 1.
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize] = [
+    migrate_v0_to_v1,
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+    migrate_v4_to_v5,
+    migrate_v5_to_v6,
+    migrate_v6_to_v7,
+    migrate_v7_to_v8,
+    migrate_v8_to_v9,
+];
+
+/// Runs every migration step needed to bring the save up to the current version.
+pub fn migrate(save_file: &mut SaveFile) -> Result<(), SaveError> {
+    if save_file.version > CURRENT_VERSION {
+        return Err(SaveError::UnsupportedVersion(save_file.version));
+    }
+
+    while save_file.version < CURRENT_VERSION {
+        MIGRATIONS[save_file.version as usize](save_file);
+        save_file.version += 1;
+    }
+
+    Ok(())
+}
+
+/// Version 0 saves were written before save slots existed and contain no metadata.
+fn migrate_v0_to_v1(save_file: &mut SaveFile) {
+    save_file.metadata.name = "Old Save".to_string();
+    save_file.metadata.difficulty = save_file.data.world_manager.difficulty();
+}
+
+/// Version 2 added the player's inventory. Older saves start out with an empty one, which the
+/// field's default already takes care of.
+fn migrate_v1_to_v2(_save_file: &mut SaveFile) {}
+
+/// Version 3 added hunger, thirst and stamina. Older saves leave them out, so the player keeps
+/// the full needs they were spawned with.
+fn migrate_v2_to_v3(_save_file: &mut SaveFile) {}
+
+/// Version 4 made trees and rocks harvestable. Obstacles in older saves were never harvested, so
+/// they keep the default of not waiting to grow back.
+fn migrate_v3_to_v4(_save_file: &mut SaveFile) {}
+
+/// Version 5 added structures built by the player. Older saves can't contain any, so their chunks
+/// start out without structures.
+fn migrate_v4_to_v5(_save_file: &mut SaveFile) {}
+
+/// Version 6 added the day/night cycle. The clock of older saves is wound forward by the time
+/// that was played, as if it had been running all along.
+fn migrate_v5_to_v6(save_file: &mut SaveFile) {
+    let mut clock = WorldClock::default();
+    clock.advance(save_file.metadata.play_time);
+    save_file.data.clock = clock;
+}
+
+/// Version 7 turned the player's attack into weapon items. Players of older saves fought with
+/// what's now the starting weapon, so they get one. It ends up on the hotbar once the game runs.
+fn migrate_v6_to_v7(save_file: &mut SaveFile) {
+    let leftover = save_file.data.player.inventory.add(STARTING_WEAPON, 1, 1);
+
+    if leftover > 0 {
+        println!("the inventory is full, the starting weapon couldn't be added to the old save");
+    }
+}
+
+/// Version 8 added armor. Older saves don't wear any, and armor found in the inventory later is
+/// put on automatically.
+fn migrate_v7_to_v8(_save_file: &mut SaveFile) {}
+
+/// Version 9 gave NPCs a home to return to. NPCs from older saves don't remember one, so they
+/// settle down wherever they are when the save is loaded.
+fn migrate_v8_to_v9(_save_file: &mut SaveFile) {}
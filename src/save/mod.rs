@@ -0,0 +1,386 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    asset::npc::NpcId,
+    building::{Door, StoredStructure, Structure, STRUCTURE_FOOTPRINT},
+    character::{
+        npc::NpcController,
+        player::Player,
+        survival::{Hunger, Stamina, Thirst},
+        Character, HealthRegen,
+    },
+    inventory::{equipment::Equipment, Inventory},
+    state::GameState,
+    world::{
+        chunk::{ChunkCoord, ChunkManager, StoredChunk, StoredNpc},
+        clock::WorldClock,
+        obstacle::{Depleted, Obstacle, StoredObstacle},
+        placement::PlacementGrid,
+        populate_with_npcs,
+        seed::WorldSeed,
+        WorldManager,
+    },
+};
+
+use self::{autosave::AutosaveSettings, slot::SaveMetadata};
+
+pub mod autosave;
+pub mod migration;
+pub mod slot;
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(autosave::AutosavePlugin)
+            .add_event::<SaveGameEvent>()
+            .init_resource::<SaveSession>()
+            .add_systems(
+                OnEnter(GameState::PreparingNpcs),
+                apply_pending_load.before(populate_with_npcs),
+            )
+            .add_systems(
+                Update,
+                (
+                    track_play_time.run_if(in_state(GameState::InGame)),
+                    save_game_event,
+                ),
+            );
+    }
+}
+
+/// Requests the running game to be written to its save slot.
+#[derive(Event)]
+pub struct SaveGameEvent(pub SaveKind);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SaveKind {
+    /// Overwrites the slot itself.
+    Manual,
+    /// Writes one of the rotating backups next to the slot.
+    Autosave,
+}
+
+/// Keeps track of the slot the running game belongs to.
+#[derive(Resource, Default)]
+pub struct SaveSession {
+    /// The slot the game was loaded from or last saved to.
+    /// A new slot is created on the first save when it's `None`.
+    pub slot: Option<String>,
+    pub name: String,
+    /// The time spent in-game, in seconds.
+    pub play_time: f32,
+}
+
+impl SaveSession {
+    /// Continues a game that was loaded from the given slot.
+    /// Loading an autosave continues the slot it was made for.
+    pub fn from_slot(slot: String, metadata: &SaveMetadata) -> Self {
+        Self {
+            slot: Some(metadata.autosave_of.clone().unwrap_or(slot)),
+            name: metadata.name.clone(),
+            play_time: metadata.play_time,
+        }
+    }
+}
+
+/// A save that's waiting to be applied once the world has been prepared.
+#[derive(Resource)]
+pub struct PendingLoad(pub SaveData);
+
+/// Everything needed to restore a running game.
+/// The save is made out of the same components the game runs on, which can be serialized thanks
+/// to Bevy's `serialize` feature.
+#[derive(Serialize, Deserialize)]
+pub struct SaveData {
+    pub seed: u64,
+    pub world_manager: WorldManager,
+    #[serde(default)]
+    pub clock: WorldClock,
+    pub player: PlayerSave,
+    /// Every chunk that has been generated so far, including the ones that were loaded at the
+    /// time of saving.
+    pub chunks: Vec<(IVec2, StoredChunk)>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PlayerSave {
+    pub position: Vec2,
+    pub character: Character,
+    pub health_regen: HealthRegen,
+    #[serde(default)]
+    pub inventory: Inventory,
+    #[serde(default)]
+    pub equipment: Equipment,
+    #[serde(default)]
+    pub survival: Option<SurvivalSave>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SurvivalSave {
+    pub hunger: Hunger,
+    pub thirst: Thirst,
+    pub stamina: Stamina,
+}
+
+/// The player's survival needs as queried from the world.
+type SurvivalQuery<'a> = (Option<&'a Hunger>, Option<&'a Thirst>, Option<&'a Stamina>);
+
+/// Everything about a structure that gets saved, as queried from the world.
+type StructureQuery<'a> = (
+    &'a Structure,
+    &'a ChunkCoord,
+    &'a Transform,
+    Option<&'a Door>,
+    Option<&'a Inventory>,
+);
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum SaveError {
+    #[error("Could not access the save file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not write RON: {0}")]
+    RonError(#[from] ron::Error),
+    /// A [RON](ron) Error
+    #[error("Could not parse RON: {0}")]
+    RonSpannedError(#[from] ron::error::SpannedError),
+    #[error("The world has no player to save")]
+    NoPlayer,
+    #[error("The save was written by a newer version of the game (format version {0})")]
+    UnsupportedVersion(u32),
+}
+
+fn track_play_time(time: Res<Time>, mut save_session: ResMut<SaveSession>) {
+    save_session.play_time += time.delta_seconds();
+}
+
+fn save_game_event(
+    mut save_events: EventReader<SaveGameEvent>,
+    mut save_session: ResMut<SaveSession>,
+    autosave_settings: Res<AutosaveSettings>,
+    world_seed: Option<Res<WorldSeed>>,
+    world_manager: Option<Res<WorldManager>>,
+    world_clock: Option<Res<WorldClock>>,
+    chunk_manager: Option<Res<ChunkManager>>,
+    player_query: Query<
+        (
+            &Transform,
+            &Character,
+            &HealthRegen,
+            &Inventory,
+            &Equipment,
+            SurvivalQuery,
+        ),
+        With<Player>,
+    >,
+    npc_query: Query<(&NpcId, &ChunkCoord, &Transform, &Character, &NpcController)>,
+    obstacle_query: Query<(&Obstacle, &ChunkCoord, &Transform, Option<&Depleted>)>,
+    structure_query: Query<StructureQuery>,
+) {
+    for SaveGameEvent(kind) in save_events.read() {
+        let (Some(world_seed), Some(world_manager), Some(world_clock), Some(chunk_manager)) =
+            (&world_seed, &world_manager, &world_clock, &chunk_manager)
+        else {
+            println!("there is no running game to save");
+            continue;
+        };
+
+        let result = collect_save(
+            world_seed,
+            world_manager,
+            world_clock,
+            chunk_manager,
+            &player_query,
+            &npc_query,
+            &obstacle_query,
+            &structure_query,
+        )
+        .and_then(|save_data| {
+            if save_session.slot.is_none() {
+                let (slot, name) = slot::new_slot();
+                save_session.slot = Some(slot);
+                save_session.name = name;
+            }
+
+            let slot = save_session.slot.as_deref().unwrap_or_default();
+            let metadata = SaveMetadata::new(
+                save_session.name.clone(),
+                save_session.play_time,
+                world_manager.difficulty(),
+            );
+
+            match kind {
+                SaveKind::Manual => slot::write_slot(slot, metadata, save_data),
+                SaveKind::Autosave => {
+                    slot::write_autosave(slot, autosave_settings.kept, metadata, save_data)
+                }
+            }
+        });
+
+        match result {
+            Ok(()) => println!(
+                "saved the game to slot {} ({kind:?})",
+                save_session.slot.as_deref().unwrap_or_default()
+            ),
+            Err(error) => println!("failed to save the game: {error}"),
+        }
+    }
+}
+
+fn collect_save(
+    world_seed: &WorldSeed,
+    world_manager: &WorldManager,
+    world_clock: &WorldClock,
+    chunk_manager: &ChunkManager,
+    player_query: &Query<
+        (
+            &Transform,
+            &Character,
+            &HealthRegen,
+            &Inventory,
+            &Equipment,
+            SurvivalQuery,
+        ),
+        With<Player>,
+    >,
+    npc_query: &Query<(&NpcId, &ChunkCoord, &Transform, &Character, &NpcController)>,
+    obstacle_query: &Query<(&Obstacle, &ChunkCoord, &Transform, Option<&Depleted>)>,
+    structure_query: &Query<StructureQuery>,
+) -> Result<SaveData, SaveError> {
+    let (
+        player_transform,
+        character,
+        health_regen,
+        inventory,
+        equipment,
+        (hunger, thirst, stamina),
+    ) = player_query.get_single().map_err(|_| SaveError::NoPlayer)?;
+
+    let mut chunks = chunk_manager
+        .stored_chunks()
+        .map(|(coord, contents)| (*coord, contents.clone()))
+        .collect::<Vec<_>>();
+
+    // The loaded chunks are saved as if they were unloaded right now
+    for chunk in chunk_manager.loaded_chunks() {
+        let mut contents = StoredChunk::default();
+
+        for (obstacle, obstacle_chunk, transform, depleted) in obstacle_query.iter() {
+            if *obstacle_chunk == chunk {
+                contents
+                    .obstacles
+                    .push(StoredObstacle::new(obstacle, transform, depleted));
+            }
+        }
+
+        for (npc_id, npc_chunk, transform, character, controller) in npc_query.iter() {
+            if *npc_chunk == chunk {
+                contents
+                    .npcs
+                    .push(StoredNpc::new(npc_id, transform, character, controller));
+            }
+        }
+
+        for (structure, structure_chunk, transform, door, inventory) in structure_query.iter() {
+            if *structure_chunk == chunk {
+                contents
+                    .structures
+                    .push(StoredStructure::new(structure, transform, door, inventory));
+            }
+        }
+
+        chunks.push((chunk.0, contents));
+    }
+
+    Ok(SaveData {
+        seed: world_seed.0,
+        world_manager: world_manager.clone(),
+        clock: world_clock.clone(),
+        player: PlayerSave {
+            position: player_transform.translation.truncate(),
+            character: character.clone(),
+            health_regen: health_regen.clone(),
+            inventory: inventory.clone(),
+            equipment: equipment.clone(),
+            survival: match (hunger, thirst, stamina) {
+                (Some(hunger), Some(thirst), Some(stamina)) => Some(SurvivalSave {
+                    hunger: hunger.clone(),
+                    thirst: thirst.clone(),
+                    stamina: stamina.clone(),
+                }),
+                _ => None,
+            },
+        },
+        chunks,
+    })
+}
+
+/// Replaces the freshly prepared world with the contents of the save.
+/// It runs before the chunks around the player get loaded, so that the saved chunks are restored
+/// instead of being generated again.
+fn apply_pending_load(
+    mut commands: Commands,
+    pending_load: Option<Res<PendingLoad>>,
+    mut world_manager: ResMut<WorldManager>,
+    mut world_clock: ResMut<WorldClock>,
+    mut chunk_manager: ResMut<ChunkManager>,
+    mut placement_grid: ResMut<PlacementGrid>,
+    mut player_query: Query<
+        (
+            &mut Transform,
+            &mut Character,
+            &mut HealthRegen,
+            &mut Inventory,
+            &mut Equipment,
+        ),
+        With<Player>,
+    >,
+    mut survival_query: Query<(&mut Hunger, &mut Thirst, &mut Stamina), With<Player>>,
+) {
+    let Some(pending_load) = pending_load else {
+        return;
+    };
+
+    let save_data = &pending_load.0;
+
+    *world_manager = save_data.world_manager.clone();
+    *world_clock = save_data.clock.clone();
+
+    if let Ok((mut transform, mut character, mut health_regen, mut inventory, mut equipment)) =
+        player_query.get_single_mut()
+    {
+        transform.translation = save_data.player.position.extend(transform.translation.z);
+        *character = save_data.player.character.clone();
+        *health_regen = save_data.player.health_regen.clone();
+        *inventory = save_data.player.inventory.clone();
+        *equipment = save_data.player.equipment.clone();
+    }
+
+    if let (Some(survival), Ok((mut hunger, mut thirst, mut stamina))) =
+        (&save_data.player.survival, survival_query.get_single_mut())
+    {
+        *hunger = survival.hunger.clone();
+        *thirst = survival.thirst.clone();
+        *stamina = survival.stamina.clone();
+    }
+
+    for (coord, contents) in save_data.chunks.iter() {
+        for stored_obstacle in contents.obstacles.iter() {
+            placement_grid.insert(
+                stored_obstacle.position,
+                stored_obstacle.obstacle.kind.footprint_radius(),
+            );
+        }
+
+        for stored_structure in contents.structures.iter() {
+            placement_grid.insert(stored_structure.position, STRUCTURE_FOOTPRINT);
+        }
+
+        chunk_manager.store(*coord, contents.clone());
+    }
+
+    commands.remove_resource::<PendingLoad>();
+}
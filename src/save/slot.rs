@@ -0,0 +1,273 @@
+use std::{
+    cmp::Reverse,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    migration::{self, CURRENT_VERSION},
+    SaveData, SaveError,
+};
+
+pub const SAVE_DIRECTORY: &str = "saves";
+const SAVE_EXTENSION: &str = "ron";
+/// Saves are written to a temporary file first, which then replaces the slot.
+const TEMPORARY_EXTENSION: &str = "tmp";
+
+/// The file written to a save slot.
+#[derive(Serialize, Deserialize)]
+pub struct SaveFile {
+    /// The version of the save format, used for picking the migrations to run.
+    pub version: u32,
+    pub metadata: SaveMetadata,
+    pub data: SaveData,
+}
+
+/// The start of a [`SaveFile`], read on its own when only the metadata is needed. The rest of the
+/// file is skipped over without building the world data.
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+    metadata: SaveMetadata,
+}
+
+/// Information about a save that's shown on the main menu.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SaveMetadata {
+    pub name: String,
+    /// The time spent in-game, in seconds.
+    pub play_time: f32,
+    /// The highest difficulty reached.
+    pub difficulty: f32,
+    /// When the save was written, in seconds since the unix epoch.
+    pub saved_at: u64,
+    /// The slot this save is a backup of, if it was written by an autosave.
+    #[serde(default)]
+    pub autosave_of: Option<String>,
+}
+
+impl SaveMetadata {
+    pub fn new(name: String, play_time: f32, difficulty: f32) -> Self {
+        let saved_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        Self {
+            name,
+            play_time,
+            difficulty,
+            saved_at,
+            autosave_of: None,
+        }
+    }
+
+    /// Describes the save in a single line, e.g. `Save 1 - 0:42:10 - difficulty 3.1 - 2024-01-20 17:03`.
+    pub fn summary(&self) -> String {
+        let play_time = self.play_time as u64;
+
+        let autosave = if self.autosave_of.is_some() {
+            " (Autosave)"
+        } else {
+            ""
+        };
+
+        format!(
+            "{}{autosave} - {}:{:02}:{:02} - difficulty {:.1} - {}",
+            self.name,
+            play_time / 3600,
+            (play_time / 60) % 60,
+            play_time % 60,
+            self.difficulty,
+            format_timestamp(self.saved_at),
+        )
+    }
+}
+
+/// A save slot found in the save directory.
+pub struct SlotInfo {
+    pub id: String,
+    pub metadata: SaveMetadata,
+}
+
+fn slot_path(slot: &str) -> PathBuf {
+    PathBuf::from(SAVE_DIRECTORY).join(format!("{slot}.{SAVE_EXTENSION}"))
+}
+
+/// The id of an autosave of the given slot. Index 0 is the most recent autosave.
+fn autosave_slot(slot: &str, index: usize) -> String {
+    format!("{slot}-autosave-{index}")
+}
+
+/// Returns an id that isn't taken by any slot yet, along with a name for the slot.
+pub fn new_slot() -> (String, String) {
+    let mut number = 1;
+
+    // A slot that has only been autosaved so far is taken as well
+    while slot_path(&format!("save-{number}")).exists()
+        || slot_path(&autosave_slot(&format!("save-{number}"), 0)).exists()
+    {
+        number += 1;
+    }
+
+    (format!("save-{number}"), format!("Save {number}"))
+}
+
+/// Lists every readable save slot, with the most recent one first.
+/// Slots that can't be read are reported and skipped.
+pub fn list_slots() -> Vec<SlotInfo> {
+    let Ok(entries) = fs::read_dir(SAVE_DIRECTORY) else {
+        return Vec::new();
+    };
+
+    let mut slots = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == SAVE_EXTENSION))
+        .filter_map(|path| {
+            let id = path.file_stem()?.to_string_lossy().to_string();
+
+            match read_metadata(&id) {
+                Ok(metadata) => Some(SlotInfo { id, metadata }),
+                Err(error) => {
+                    println!("skipping save slot {id}: {error}");
+                    None
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    slots.sort_by_key(|slot| Reverse(slot.metadata.saved_at));
+    slots
+}
+
+/// Reads only the metadata of a save slot.
+fn read_metadata(slot: &str) -> Result<SaveMetadata, SaveError> {
+    let contents = fs::read_to_string(slot_path(slot))?;
+
+    match ron::from_str::<SaveHeader>(&contents) {
+        Ok(header) if header.version > CURRENT_VERSION => {
+            Err(SaveError::UnsupportedVersion(header.version))
+        }
+        Ok(header) => Ok(header.metadata),
+        // Saves from before versioning don't have a header, their metadata is made up from the
+        // data by the migrations
+        Err(_) => read_slot(slot).map(|save_file| save_file.metadata),
+    }
+}
+
+/// Reads a save slot and upgrades it to the current version.
+pub fn read_slot(slot: &str) -> Result<SaveFile, SaveError> {
+    let contents = fs::read_to_string(slot_path(slot))?;
+    let mut save_file = parse_save(&contents)?;
+
+    migration::migrate(&mut save_file)?;
+
+    Ok(save_file)
+}
+
+/// Parses a save file. Saves written before versioning was introduced only contain the
+/// [`SaveData`], so they're treated as version 0.
+fn parse_save(contents: &str) -> Result<SaveFile, SaveError> {
+    match ron::from_str::<SaveFile>(contents) {
+        Ok(save_file) => Ok(save_file),
+        Err(error) => match ron::from_str::<SaveData>(contents) {
+            Ok(data) => Ok(SaveFile {
+                version: 0,
+                metadata: SaveMetadata::default(),
+                data,
+            }),
+            Err(_) => Err(error.into()),
+        },
+    }
+}
+
+pub fn write_slot(slot: &str, metadata: SaveMetadata, data: SaveData) -> Result<(), SaveError> {
+    let save_file = SaveFile {
+        version: CURRENT_VERSION,
+        metadata,
+        data,
+    };
+
+    let contents = ron::ser::to_string_pretty(&save_file, ron::ser::PrettyConfig::default())?;
+
+    fs::create_dir_all(SAVE_DIRECTORY)?;
+    write_atomically(&slot_path(slot), contents.as_bytes())?;
+
+    Ok(())
+}
+
+/// Writes an autosave of the given slot, keeping the last `kept` autosaves around as backups.
+/// The oldest autosave is dropped and every other one moves up by one index.
+pub fn write_autosave(
+    slot: &str,
+    kept: usize,
+    mut metadata: SaveMetadata,
+    data: SaveData,
+) -> Result<(), SaveError> {
+    let kept = kept.max(1);
+
+    let oldest = slot_path(&autosave_slot(slot, kept - 1));
+    if oldest.exists() {
+        fs::remove_file(oldest)?;
+    }
+
+    for index in (0..kept - 1).rev() {
+        let path = slot_path(&autosave_slot(slot, index));
+
+        if path.exists() {
+            fs::rename(path, slot_path(&autosave_slot(slot, index + 1)))?;
+        }
+    }
+
+    metadata.autosave_of = Some(slot.to_string());
+    write_slot(&autosave_slot(slot, 0), metadata, data)
+}
+
+/// Writes the file next to its destination and renames it into place once it's complete.
+/// The rename replaces the old file in a single step, so a crash can't leave a half written save
+/// behind.
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), SaveError> {
+    let temporary_path = path.with_extension(format!("{SAVE_EXTENSION}.{TEMPORARY_EXTENSION}"));
+
+    let mut file = fs::File::create(&temporary_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+
+    fs::rename(temporary_path, path)?;
+
+    Ok(())
+}
+
+/// Formats a unix timestamp as an UTC date, e.g. `2024-01-20 17:03`.
+fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+
+    // Converts the days since the epoch into a civil date
+    // See: http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{year}-{month:02}-{day:02} {:02}:{:02}",
+        seconds / 3600,
+        (seconds / 60) % 60
+    )
+}
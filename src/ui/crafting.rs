@@ -0,0 +1,242 @@
+use bevy::prelude::*;
+
+use crate::{
+    asset::recipe::RecipeData,
+    character::player::Player,
+    crafting::{self, CraftEvent, Crafting, RecipeBook, Workstation},
+    inventory::{Inventory, ItemRegistry},
+    state::GameState,
+    world::WorldObject,
+};
+
+use super::NORMAL_BUTTON;
+
+pub struct CraftingUiPlugin;
+
+impl Plugin for CraftingUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                toggle_crafting_window,
+                recipe_button_click,
+                update_crafting_window,
+            )
+                .run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+const CRAFTABLE_TEXT: Color = Color::WHITE;
+const UNCRAFTABLE_TEXT: Color = Color::GRAY;
+
+#[derive(Component)]
+struct CraftingWindow;
+
+/// Starts crafting the recipe with the given id.
+#[derive(Component)]
+struct RecipeButton(String);
+
+/// Shows what's currently being crafted.
+#[derive(Component)]
+struct CraftingStatusText;
+
+fn recipe_label(recipe: &RecipeData, item_registry: &ItemRegistry) -> String {
+    let item_name = |id: &String| {
+        item_registry
+            .get(id)
+            .map(|item| item.name.clone())
+            .unwrap_or_else(|| id.clone())
+    };
+
+    let inputs = recipe
+        .inputs
+        .iter()
+        .map(|(item, amount)| format!("{amount} {}", item_name(item)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    match recipe.workstation {
+        Some(workstation) => format!("{} ({inputs}) at {workstation:?}", recipe.name),
+        None => format!("{} ({inputs})", recipe.name),
+    }
+}
+
+fn toggle_crafting_window(
+    mut commands: Commands,
+    key_input: Res<Input<KeyCode>>,
+    window_query: Query<Entity, With<CraftingWindow>>,
+    recipe_book: Res<RecipeBook>,
+    item_registry: Res<ItemRegistry>,
+) {
+    if !key_input.just_pressed(KeyCode::C) {
+        return;
+    }
+
+    if let Ok(window) = window_query.get_single() {
+        commands.entity(window).despawn_recursive();
+        return;
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(20.0),
+                    top: Val::Px(20.0),
+                    padding: UiRect::all(Val::Px(10.0)),
+                    row_gap: Val::Px(8.0),
+                    flex_direction: FlexDirection::Column,
+                    border: UiRect::all(Val::Px(5.0)),
+                    ..Default::default()
+                },
+                background_color: Color::DARK_GRAY.into(),
+                border_color: Color::BLACK.into(),
+                ..Default::default()
+            },
+            CraftingWindow,
+            WorldObject,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Crafting",
+                TextStyle {
+                    font_size: 30.0,
+                    ..Default::default()
+                },
+            ));
+
+            for recipe in recipe_book.iter() {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::all(Val::Px(6.0)),
+                                border: UiRect::all(Val::Px(3.0)),
+                                ..Default::default()
+                            },
+                            border_color: BorderColor(Color::BLACK),
+                            background_color: NORMAL_BUTTON.into(),
+                            ..Default::default()
+                        },
+                        RecipeButton(recipe.id.clone()),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            recipe_label(recipe, &item_registry),
+                            TextStyle {
+                                font_size: 20.0,
+                                color: UNCRAFTABLE_TEXT,
+                                ..Default::default()
+                            },
+                        ));
+                    });
+            }
+
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 20.0,
+                        ..Default::default()
+                    },
+                ),
+                CraftingStatusText,
+            ));
+        });
+}
+
+fn recipe_button_click(
+    button_query: Query<(&Interaction, &RecipeButton), (Changed<Interaction>, With<Button>)>,
+    player_query: Query<Entity, With<Player>>,
+    mut craft_event_writer: EventWriter<CraftEvent>,
+) {
+    let Ok(player) = player_query.get_single() else {
+        return;
+    };
+
+    for (interaction, recipe_button) in button_query.iter() {
+        if *interaction == Interaction::Pressed {
+            craft_event_writer.send(CraftEvent {
+                entity: player,
+                recipe: recipe_button.0.clone(),
+            });
+        }
+    }
+}
+
+/// Greys out the recipes that can't be crafted right now and shows the crafting progress, or the
+/// description of what the hovered recipe makes.
+fn update_crafting_window(
+    player_query: Query<(&Inventory, &Transform, Option<&Crafting>), With<Player>>,
+    workstation_query: Query<(&Workstation, &GlobalTransform)>,
+    button_query: Query<(&RecipeButton, &Interaction, &Children)>,
+    mut text_query: Query<&mut Text, Without<CraftingStatusText>>,
+    mut status_query: Query<&mut Text, With<CraftingStatusText>>,
+    recipe_book: Res<RecipeBook>,
+    item_registry: Res<ItemRegistry>,
+) {
+    let Ok((inventory, transform, crafting)) = player_query.get_single() else {
+        return;
+    };
+
+    let mut hovered_description = None;
+
+    for (recipe_button, interaction, children) in button_query.iter() {
+        let Some(recipe) = recipe_book.get(&recipe_button.0) else {
+            continue;
+        };
+
+        if *interaction == Interaction::Hovered {
+            hovered_description = recipe
+                .outputs
+                .first()
+                .and_then(|(item, _)| item_registry.get(item))
+                .map(|item| item.description.clone());
+        }
+
+        let workstations = workstation_query
+            .iter()
+            .map(|(workstation, transform)| (transform.translation().truncate(), workstation.0));
+
+        let craftable = crafting::has_inputs(inventory, recipe)
+            && crafting::workstation_in_range(
+                recipe,
+                transform.translation.truncate(),
+                workstations,
+            );
+
+        let color = if craftable {
+            CRAFTABLE_TEXT
+        } else {
+            UNCRAFTABLE_TEXT
+        };
+
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                // Only touching the text when it changes avoids re-laying it out every frame
+                if text.sections[0].style.color != color {
+                    text.sections[0].style.color = color;
+                }
+            }
+        }
+    }
+
+    let status = match crafting.and_then(|crafting| {
+        recipe_book
+            .get(&crafting.recipe)
+            .map(|recipe| (recipe, crafting.timer.percent()))
+    }) {
+        Some((recipe, progress)) => {
+            format!("Crafting {}... {:.0}%", recipe.name, progress * 100.0)
+        }
+        None => hovered_description.unwrap_or_else(|| "Press C to close".to_string()),
+    };
+
+    for mut text in status_query.iter_mut() {
+        if text.sections[0].value != status {
+            text.sections[0].value = status.clone();
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use bevy::prelude::*;
+
+use crate::{
+    asset::GameSprites,
+    building::{BuildMode, StructureRegistry},
+    character::{
+        player::Player,
+        survival::{Hunger, Stamina, Thirst},
+    },
+    inventory::{
+        equipment::{Equipment, HOTBAR_SIZE},
+        ItemRegistry,
+    },
+    state::GameState,
+    world::{clock::WorldClock, WorldObject},
+};
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::PreparingNpcs), spawn_hud)
+            .add_systems(
+                Update,
+                (
+                    update_hud,
+                    update_day_text,
+                    update_build_text,
+                    update_hotbar,
+                )
+                    .run_if(in_state(GameState::InGame)),
+            );
+    }
+}
+
+const BAR_WIDTH: f32 = 200.0;
+const BAR_HEIGHT: f32 = 16.0;
+
+const HOTBAR_SLOT_SIZE: f32 = 64.0;
+const SELECTED_SLOT_BORDER: Color = Color::WHITE;
+const SLOT_BORDER: Color = Color::BLACK;
+
+/// Which value a HUD bar displays.
+#[derive(Component, Clone, Copy)]
+enum HudBar {
+    Hunger,
+    Thirst,
+    Stamina,
+}
+
+/// The filled part of a HUD bar.
+#[derive(Component)]
+struct HudBarFill(HudBar);
+
+/// A hotbar slot, showing the weapon in the slot with the same index.
+#[derive(Component)]
+struct HotbarSlot(usize);
+
+/// The picture of the weapon in a hotbar slot.
+#[derive(Component)]
+struct HotbarIcon(usize);
+
+/// Shows the current day and time of day.
+#[derive(Component)]
+struct DayText;
+
+/// Shows the structure the player is placing, if any.
+#[derive(Component)]
+struct BuildText;
+
+fn spawn_hud(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(20.0),
+                    top: Val::Px(20.0),
+                    row_gap: Val::Px(6.0),
+                    flex_direction: FlexDirection::Column,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            Name::new("Hud"),
+            // The HUD belongs to the running game and gets cleared along with the world
+            WorldObject,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.0,
+                        ..Default::default()
+                    },
+                ),
+                DayText,
+            ));
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 20.0,
+                        ..Default::default()
+                    },
+                ),
+                BuildText,
+            ));
+
+            spawn_bar(
+                parent,
+                "Hunger",
+                Color::rgb(0.85, 0.55, 0.2),
+                HudBar::Hunger,
+            );
+            spawn_bar(parent, "Thirst", Color::rgb(0.25, 0.5, 0.9), HudBar::Thirst);
+            spawn_bar(
+                parent,
+                "Stamina",
+                Color::rgb(0.4, 0.8, 0.3),
+                HudBar::Stamina,
+            );
+        });
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(20.0),
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    column_gap: Val::Px(8.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            Name::new("Hotbar"),
+            WorldObject,
+        ))
+        .with_children(|parent| {
+            for slot in 0..HOTBAR_SIZE {
+                spawn_hotbar_slot(parent, slot);
+            }
+        });
+}
+
+fn spawn_bar(parent: &mut ChildBuilder, label: &str, color: Color, bar: HudBar) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(10.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle {
+                text: Text::from_section(
+                    label,
+                    TextStyle {
+                        font_size: 20.0,
+                        ..Default::default()
+                    },
+                ),
+                style: Style {
+                    width: Val::Px(80.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(BAR_WIDTH),
+                        height: Val::Px(BAR_HEIGHT),
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..Default::default()
+                    },
+                    background_color: Color::DARK_GRAY.into(),
+                    border_color: Color::BLACK.into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(100.0),
+                                height: Val::Percent(100.0),
+                                ..Default::default()
+                            },
+                            background_color: color.into(),
+                            ..Default::default()
+                        },
+                        HudBarFill(bar),
+                    ));
+                });
+        });
+}
+
+fn spawn_hotbar_slot(parent: &mut ChildBuilder, slot: usize) {
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Px(HOTBAR_SLOT_SIZE),
+                    height: Val::Px(HOTBAR_SLOT_SIZE),
+                    border: UiRect::all(Val::Px(3.0)),
+                    padding: UiRect::all(Val::Px(4.0)),
+                    ..Default::default()
+                },
+                background_color: Color::DARK_GRAY.with_a(0.8).into(),
+                border_color: SLOT_BORDER.into(),
+                ..Default::default()
+            },
+            HotbarSlot(slot),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                ImageBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..Default::default()
+                    },
+                    visibility: Visibility::Hidden,
+                    ..Default::default()
+                },
+                HotbarIcon(slot),
+            ));
+
+            // The key that selects the slot
+            parent.spawn(TextBundle {
+                text: Text::from_section(
+                    (slot + 1).to_string(),
+                    TextStyle {
+                        font_size: 16.0,
+                        ..Default::default()
+                    },
+                ),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(2.0),
+                    top: Val::Px(0.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+        });
+}
+
+fn update_hud(
+    player_query: Query<(Option<&Hunger>, Option<&Thirst>, Option<&Stamina>), With<Player>>,
+    mut fill_query: Query<(&mut Style, &HudBarFill)>,
+) {
+    let Ok((hunger, thirst, stamina)) = player_query.get_single() else {
+        return;
+    };
+
+    for (mut style, fill) in fill_query.iter_mut() {
+        let fraction = match fill.0 {
+            HudBar::Hunger => hunger.map(|hunger| hunger.0.value / hunger.0.max),
+            HudBar::Thirst => thirst.map(|thirst| thirst.0.value / thirst.0.max),
+            HudBar::Stamina => stamina.map(|stamina| stamina.value / stamina.max),
+        };
+
+        style.width = Val::Percent(fraction.unwrap_or(0.0).clamp(0.0, 1.0) * 100.0);
+    }
+}
+
+fn update_day_text(world_clock: Res<WorldClock>, mut text_query: Query<&mut Text, With<DayText>>) {
+    let day_text = format!(
+        "Day {} - {:?}",
+        world_clock.day(),
+        world_clock.time_of_day()
+    );
+
+    for mut text in text_query.iter_mut() {
+        // Only touching the text when it changes avoids re-laying it out every frame
+        if text.sections[0].value != day_text {
+            text.sections[0].value = day_text.clone();
+        }
+    }
+}
+
+fn update_build_text(
+    build_mode: Res<BuildMode>,
+    structure_registry: Res<StructureRegistry>,
+    mut text_query: Query<&mut Text, With<BuildText>>,
+) {
+    if !build_mode.is_changed() {
+        return;
+    }
+
+    let build_text = build_mode
+        .structure
+        .as_deref()
+        .and_then(|structure| structure_registry.get(structure))
+        .map(|structure| format!("Building: {}", structure.name))
+        .unwrap_or_default();
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = build_text.clone();
+    }
+}
+
+fn update_hotbar(
+    player_query: Query<&Equipment, (With<Player>, Changed<Equipment>)>,
+    mut slot_query: Query<(&HotbarSlot, &mut BorderColor)>,
+    mut icon_query: Query<(&HotbarIcon, &mut UiImage, &mut Visibility)>,
+    mut game_sprites: ResMut<GameSprites>,
+    asset_server: Res<AssetServer>,
+    item_registry: Res<ItemRegistry>,
+) {
+    let Ok(equipment) = player_query.get_single() else {
+        return;
+    };
+
+    for (slot, mut border_color) in slot_query.iter_mut() {
+        border_color.0 = if slot.0 == equipment.selected {
+            SELECTED_SLOT_BORDER
+        } else {
+            SLOT_BORDER
+        };
+    }
+
+    for (icon, mut image, mut visibility) in icon_query.iter_mut() {
+        let item = equipment
+            .hotbar
+            .get(icon.0)
+            .and_then(|item| item.as_ref())
+            .and_then(|item| item_registry.get(item));
+
+        match item {
+            Some(item) => {
+                image.texture = game_sprites.get_or_load(&item.sprite_name, &asset_server);
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
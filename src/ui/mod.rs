@@ -1,20 +1,44 @@
 use bevy::{app::AppExit, prelude::*};
 
-use crate::{asset::TitleImage, state::GameState, world::ClearWorldEvent};
+use crate::{
+    asset::TitleImage,
+    save::{
+        slot::{self, SlotInfo},
+        PendingLoad, SaveGameEvent, SaveKind, SaveSession,
+    },
+    state::GameState,
+    world::{
+        clock::WorldClock,
+        seed::{self, WorldSeed},
+        ClearWorldEvent,
+    },
+};
+
+pub mod crafting;
+pub mod hud;
 
 pub struct UiPlugin;
 
 const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
 
+const MAX_SEED_LENGTH: usize = 24;
+
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::MainMenu), setup_main_menu)
+        app.add_plugins((hud::HudPlugin, crafting::CraftingUiPlugin))
+            .insert_resource(SeedInput(seed::seed_argument().unwrap_or_default()))
+            .add_systems(OnEnter(GameState::MainMenu), setup_main_menu)
             .add_systems(
                 Update,
                 (
+                    seed_input.run_if(in_state(GameState::MainMenu)),
                     button_hover,
                     start_button_click,
+                    load_button_click,
+                    slot_button_click,
+                    back_button_click,
+                    save_button_click,
                     exit_button_click,
                     continue_button_click,
                     exit_to_menu_button_click,
@@ -35,21 +59,45 @@ impl Plugin for UiPlugin {
     }
 }
 
+/// The seed typed in on the main menu. A random seed is used when it's left empty.
+#[derive(Resource)]
+struct SeedInput(String);
+
 #[derive(Component)]
 struct MainMenuContainer;
 
+#[derive(Component)]
+struct SeedText;
+
 #[derive(Component)]
 struct StartButton;
 
+#[derive(Component)]
+struct LoadButton;
+
 #[derive(Component)]
 struct ExitButton;
 
+/// Lists the save slots that can be loaded. It replaces the main menu while it's open.
+#[derive(Component)]
+struct SlotMenuContainer;
+
+/// Loads the save slot with the given id.
+#[derive(Component)]
+struct SlotButton(String);
+
+#[derive(Component)]
+struct BackButton;
+
 #[derive(Component)]
 struct PauseMenuContainer;
 
 #[derive(Component)]
 struct ResumeButton;
 
+#[derive(Component)]
+struct SaveButton;
+
 #[derive(Component)]
 struct ExitToMenuButton;
 
@@ -80,17 +128,127 @@ fn button_hover(
     }
 }
 
+fn seed_text(seed_input: &str) -> String {
+    if seed_input.is_empty() {
+        "Seed: <random>".to_string()
+    } else {
+        format!("Seed: {seed_input}")
+    }
+}
+
+fn seed_input(
+    mut character_events: EventReader<ReceivedCharacter>,
+    key_input: Res<Input<KeyCode>>,
+    mut seed_input: ResMut<SeedInput>,
+    mut seed_text_query: Query<&mut Text, With<SeedText>>,
+) {
+    for event in character_events.read() {
+        if !event.char.is_control() && seed_input.0.len() < MAX_SEED_LENGTH {
+            seed_input.0.push(event.char);
+        }
+    }
+
+    if key_input.just_pressed(KeyCode::Back) {
+        seed_input.0.pop();
+    }
+
+    if seed_input.is_changed() {
+        for mut text in seed_text_query.iter_mut() {
+            text.sections[0].value = seed_text(&seed_input.0);
+        }
+    }
+}
+
 fn start_button_click(
+    mut commands: Commands,
     button_query: Query<&Interaction, (Changed<Interaction>, With<Button>, With<StartButton>)>,
+    seed_input: Res<SeedInput>,
     mut game_state: ResMut<NextState<GameState>>,
 ) {
     for interaction in button_query.iter() {
         if *interaction == Interaction::Pressed {
+            let world_seed = if seed_input.0.trim().is_empty() {
+                WorldSeed::random()
+            } else {
+                WorldSeed::from_text(&seed_input.0)
+            };
+
+            commands.insert_resource(world_seed);
+            commands.insert_resource(SaveSession::default());
             game_state.set(GameState::LoadingAssets);
         }
     }
 }
 
+fn load_button_click(
+    mut commands: Commands,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<Button>, With<LoadButton>)>,
+    mut main_menu_query: Query<&mut Style, With<MainMenuContainer>>,
+) {
+    for interaction in button_query.iter() {
+        if *interaction == Interaction::Pressed {
+            for mut style in main_menu_query.iter_mut() {
+                style.display = Display::None;
+            }
+
+            spawn_slot_menu(&mut commands, &slot::list_slots());
+        }
+    }
+}
+
+fn slot_button_click(
+    mut commands: Commands,
+    button_query: Query<(&Interaction, &SlotButton), (Changed<Interaction>, With<Button>)>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, slot_button) in button_query.iter() {
+        if *interaction == Interaction::Pressed {
+            match slot::read_slot(&slot_button.0) {
+                Ok(save_file) => {
+                    commands.insert_resource(WorldSeed(save_file.data.seed));
+                    commands.insert_resource(SaveSession::from_slot(
+                        slot_button.0.clone(),
+                        &save_file.metadata,
+                    ));
+                    commands.insert_resource(PendingLoad(save_file.data));
+                    game_state.set(GameState::LoadingAssets);
+                }
+                Err(error) => println!("failed to load the game: {error}"),
+            }
+        }
+    }
+}
+
+fn back_button_click(
+    mut commands: Commands,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<Button>, With<BackButton>)>,
+    slot_menu_query: Query<Entity, With<SlotMenuContainer>>,
+    mut main_menu_query: Query<&mut Style, With<MainMenuContainer>>,
+) {
+    for interaction in button_query.iter() {
+        if *interaction == Interaction::Pressed {
+            for slot_menu in slot_menu_query.iter() {
+                commands.entity(slot_menu).despawn_recursive();
+            }
+
+            for mut style in main_menu_query.iter_mut() {
+                style.display = Display::Flex;
+            }
+        }
+    }
+}
+
+fn save_button_click(
+    button_query: Query<&Interaction, (Changed<Interaction>, With<Button>, With<SaveButton>)>,
+    mut save_event: EventWriter<SaveGameEvent>,
+) {
+    for interaction in button_query.iter() {
+        if *interaction == Interaction::Pressed {
+            save_event.send(SaveGameEvent(SaveKind::Manual));
+        }
+    }
+}
+
 fn exit_button_click(
     button_query: Query<&Interaction, (Changed<Interaction>, With<Button>, With<ExitButton>)>,
     mut app_exit: EventWriter<AppExit>,
@@ -139,7 +297,11 @@ fn exit_to_menu_button_click(
     }
 }
 
-fn setup_main_menu(mut commands: Commands, title_image: Res<TitleImage>) {
+fn setup_main_menu(
+    mut commands: Commands,
+    title_image: Res<TitleImage>,
+    seed_input: Res<SeedInput>,
+) {
     commands
         .spawn((
             NodeBundle {
@@ -173,6 +335,17 @@ fn setup_main_menu(mut commands: Commands, title_image: Res<TitleImage>) {
                 ..Default::default()
             });
 
+            parent.spawn((
+                TextBundle::from_section(
+                    seed_text(&seed_input.0),
+                    TextStyle {
+                        font_size: 30.0,
+                        ..Default::default()
+                    },
+                ),
+                SeedText,
+            ));
+
             parent
                 .spawn((
                     ButtonBundle {
@@ -202,6 +375,10 @@ fn setup_main_menu(mut commands: Commands, title_image: Res<TitleImage>) {
                     ));
                 });
 
+            if !slot::list_slots().is_empty() {
+                spawn_button(parent, "Load Game", 220.0, LoadButton);
+            }
+
             parent
                 .spawn((
                     ButtonBundle {
@@ -232,13 +409,75 @@ fn setup_main_menu(mut commands: Commands, title_image: Res<TitleImage>) {
         });
 }
 
-fn spawn_pause_menu(mut commands: Commands) {
+fn spawn_slot_menu(commands: &mut Commands, slots: &[SlotInfo]) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_content: AlignContent::Center,
+                    align_items: AlignItems::Center,
+                    justify_items: JustifyItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(20.0),
+                    flex_direction: FlexDirection::Column,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            SlotMenuContainer,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Load Game",
+                TextStyle {
+                    font_size: 40.0,
+                    ..Default::default()
+                },
+            ));
+
+            for slot in slots.iter() {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(700.0),
+                                height: Val::Px(50.0),
+                                border: UiRect::all(Val::Px(5.0)),
+                                justify_content: JustifyContent::Center,
+                                justify_items: JustifyItems::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            border_color: BorderColor(Color::BLACK),
+                            background_color: NORMAL_BUTTON.into(),
+                            ..Default::default()
+                        },
+                        SlotButton(slot.id.clone()),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            slot.metadata.summary(),
+                            TextStyle {
+                                font_size: 24.0,
+                                ..Default::default()
+                            },
+                        ));
+                    });
+            }
+
+            spawn_button(parent, "Back", 150.0, BackButton);
+        });
+}
+
+fn spawn_pause_menu(mut commands: Commands, world_seed: Res<WorldSeed>) {
     commands
         .spawn((
             NodeBundle {
                 style: Style {
                     width: Val::Percent(25.0),
-                    height: Val::Percent(45.0),
+                    height: Val::Percent(60.0),
                     align_content: AlignContent::Center,
                     align_items: AlignItems::Center,
                     align_self: AlignSelf::Center,
@@ -266,6 +505,14 @@ fn spawn_pause_menu(mut commands: Commands) {
                 },
             ));
 
+            parent.spawn(TextBundle::from_section(
+                format!("Seed: {}", world_seed.0),
+                TextStyle {
+                    font_size: 20.0,
+                    ..Default::default()
+                },
+            ));
+
             parent
                 .spawn((
                     ButtonBundle {
@@ -294,6 +541,8 @@ fn spawn_pause_menu(mut commands: Commands) {
                     ));
                 });
 
+            spawn_button(parent, "Save Game", 220.0, SaveButton);
+
             parent
                 .spawn((
                     ButtonBundle {
@@ -324,7 +573,7 @@ fn spawn_pause_menu(mut commands: Commands) {
         });
 }
 
-fn spawn_death_menu(mut commands: Commands) {
+fn spawn_death_menu(mut commands: Commands, world_clock: Option<Res<WorldClock>>) {
     commands
         .spawn((
             NodeBundle {
@@ -358,6 +607,19 @@ fn spawn_death_menu(mut commands: Commands) {
                 },
             ));
 
+            if let Some(world_clock) = &world_clock {
+                let days = world_clock.day();
+                let unit = if days == 1 { "day" } else { "days" };
+
+                parent.spawn(TextBundle::from_section(
+                    format!("You made it to {days} {unit}"),
+                    TextStyle {
+                        font_size: 25.0,
+                        ..Default::default()
+                    },
+                ));
+            }
+
             parent
                 .spawn((
                     ButtonBundle {
@@ -388,10 +650,48 @@ fn spawn_death_menu(mut commands: Commands) {
         });
 }
 
-fn exit_main_menu(mut commands: Commands, menu_query: Query<Entity, With<MainMenuContainer>>) {
+fn spawn_button(parent: &mut ChildBuilder, label: &str, width: f32, marker: impl Component) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(width),
+                    height: Val::Px(65.0),
+                    border: UiRect::all(Val::Px(5.0)),
+                    justify_content: JustifyContent::Center,
+                    justify_items: JustifyItems::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                border_color: BorderColor(Color::BLACK),
+                background_color: NORMAL_BUTTON.into(),
+                ..Default::default()
+            },
+            marker,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: 40.0,
+                    ..Default::default()
+                },
+            ));
+        });
+}
+
+fn exit_main_menu(
+    mut commands: Commands,
+    menu_query: Query<Entity, With<MainMenuContainer>>,
+    slot_menu_query: Query<Entity, With<SlotMenuContainer>>,
+) {
     let menu_container = menu_query.single();
 
     commands.entity(menu_container).despawn_recursive();
+
+    for slot_menu in slot_menu_query.iter() {
+        commands.entity(slot_menu).despawn_recursive();
+    }
 }
 
 fn despawn_container<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
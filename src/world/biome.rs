@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+use rand::prelude::*;
+
+use crate::asset::biome::BiomeData;
+
+/// How many world units a single cycle of the biome noise spans.
+/// Larger values result in larger biomes.
+const BIOME_SCALE: f64 = 1800.0;
+
+/// Decides which biome covers any point of the world.
+#[derive(Resource)]
+pub struct BiomeMap {
+    biomes: Vec<BiomeData>,
+    moisture: Fbm<Perlin>,
+    ruggedness: Fbm<Perlin>,
+}
+
+impl BiomeMap {
+    pub fn new(mut biomes: Vec<BiomeData>, rng: &mut StdRng) -> Self {
+        // Asset storage has no stable order, so the biomes get sorted to keep overlapping
+        // ranges resolving the same way on every run.
+        biomes.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Self {
+            biomes,
+            moisture: Fbm::<Perlin>::new(rng.gen())
+                .set_octaves(3)
+                .set_frequency(1.0 / BIOME_SCALE),
+            ruggedness: Fbm::<Perlin>::new(rng.gen())
+                .set_octaves(3)
+                .set_frequency(1.0 / BIOME_SCALE),
+        }
+    }
+
+    /// Returns the biome at the given position.
+    /// Points that aren't covered by the range of any biome fall back to the first one.
+    pub fn biome_at(&self, position: Vec2) -> Option<&BiomeData> {
+        let point = [position.x as f64, position.y as f64];
+        let moisture = self.moisture.get(point) as f32;
+        let ruggedness = self.ruggedness.get(point) as f32;
+
+        self.biomes
+            .iter()
+            .find(|biome| biome.contains(moisture, ruggedness))
+            .or(self.biomes.first())
+    }
+
+    /// Checks whether the given NPC is allowed to spawn at the given position.
+    pub fn allows_npc(&self, position: Vec2, npc_id: &str) -> bool {
+        self.biome_at(position)
+            .is_some_and(|biome| biome.npcs.iter().any(|id| id == npc_id))
+    }
+
+    /// Reports biomes that refer to NPCs which don't exist, which usually means a typo.
+    pub fn validate(&self, npc_ids: &[&str]) {
+        for biome in self.biomes.iter() {
+            for npc in biome.npcs.iter() {
+                if !npc_ids.contains(&npc.as_str()) {
+                    println!("biome \"{}\" refers to an unknown npc \"{npc}\"", biome.id);
+                }
+            }
+        }
+    }
+}
+
+/// Turns a density (the average amount of objects per cell) into the amount of objects to place.
+/// The fractional part is used as the chance of placing one more object.
+pub fn roll_density(rng: &mut StdRng, density: f32) -> usize {
+    let mut amount = density.floor() as usize;
+
+    if rng.gen::<f32>() < density.fract() {
+        amount += 1;
+    }
+
+    amount
+}
@@ -0,0 +1,476 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use rand::prelude::*;
+
+use crate::{
+    asset::{
+        biome::BiomeData,
+        npc::{NpcId, NpcSpawnInfo},
+        EnvironmentAssets, GameSprites, LoadEntity,
+    },
+    building::{self, Door, StoredStructure, Structure, StructureRegistry},
+    character::{npc::NpcController, player::Player, Character},
+    inventory::Inventory,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    biome::{self, BiomeMap},
+    obstacle::{spawn_obstacle, Depleted, Obstacle, ObstacleKind, StoredObstacle},
+    placement::{self, PlacementGrid},
+    seed::WorldSeed,
+    NpcPool, WorldManager, GENERATION_CELL_SIZE,
+};
+
+/// The width and height of a single chunk in world units.
+pub const CHUNK_SIZE: f32 = 1000.0;
+
+/// Chunks that are at most this many chunks away from the player are kept loaded.
+const LOAD_RADIUS: i32 = 2;
+/// Chunks that are further away than this many chunks from the player get unloaded.
+/// It's larger than [`LOAD_RADIUS`], so that walking back and forth over a chunk border doesn't
+/// keep loading and unloading the same chunks.
+const UNLOAD_RADIUS: i32 = 3;
+
+/// The average amount of NPCs placed in a freshly generated chunk.
+const NPC_DENSITY: f32 = 2.5;
+
+/// The chunk an entity belongs to.
+/// Entities get stored or despawned together with their chunk.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ChunkCoord(pub IVec2);
+
+impl ChunkCoord {
+    pub fn from_position(position: Vec2) -> Self {
+        Self((position / CHUNK_SIZE).floor().as_ivec2())
+    }
+
+    /// The bottom-left corner of the chunk.
+    pub fn origin(&self) -> Vec2 {
+        self.0.as_vec2() * CHUNK_SIZE
+    }
+
+    pub fn area(&self) -> Rect {
+        Rect::from_corners(self.origin(), self.origin() + CHUNK_SIZE)
+    }
+
+    /// The distance to another chunk, counted in chunks.
+    pub fn distance(&self, other: ChunkCoord) -> i32 {
+        (self.0 - other.0).abs().max_element()
+    }
+}
+
+/// An NPC that is kept in memory while the chunk it belongs to is unloaded.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StoredNpc {
+    pub id: String,
+    pub position: Vec2,
+    pub character: Character,
+    pub controller: NpcController,
+}
+
+impl StoredNpc {
+    pub fn new(
+        npc_id: &NpcId,
+        transform: &Transform,
+        character: &Character,
+        controller: &NpcController,
+    ) -> Self {
+        Self {
+            id: npc_id.0.clone(),
+            position: transform.translation.truncate(),
+            character: character.clone(),
+            controller: controller.detached(),
+        }
+    }
+}
+
+/// The contents of a chunk that has been generated, but isn't loaded at the moment.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct StoredChunk {
+    pub obstacles: Vec<StoredObstacle>,
+    pub npcs: Vec<StoredNpc>,
+    #[serde(default)]
+    pub structures: Vec<StoredStructure>,
+}
+
+/// Keeps track of which chunks are loaded and what the unloaded chunks contain.
+#[derive(Resource, Default)]
+pub struct ChunkManager {
+    loaded: HashSet<IVec2>,
+    stored: HashMap<IVec2, StoredChunk>,
+}
+
+impl ChunkManager {
+    pub fn is_loaded(&self, chunk: ChunkCoord) -> bool {
+        self.loaded.contains(&chunk.0)
+    }
+
+    /// Returns the chunks that have been generated, but aren't loaded at the moment.
+    pub fn stored_chunks(&self) -> impl Iterator<Item = (&IVec2, &StoredChunk)> {
+        self.stored.iter()
+    }
+
+    /// Replaces the contents of a chunk with the given ones. The chunk gets restored from them
+    /// the next time it's loaded.
+    pub fn store(&mut self, chunk: IVec2, contents: StoredChunk) {
+        self.stored.insert(chunk, contents);
+    }
+
+    /// Returns the loaded chunks in a stable order, so that picking one at random stays
+    /// reproducible.
+    pub fn loaded_chunks(&self) -> Vec<ChunkCoord> {
+        let mut chunks = self
+            .loaded
+            .iter()
+            .copied()
+            .map(ChunkCoord)
+            .collect::<Vec<_>>();
+        chunks.sort_by_key(|chunk| (chunk.0.x, chunk.0.y));
+        chunks
+    }
+}
+
+/// Keeps the chunk of every NPC up to date as it walks around.
+pub fn update_chunk_membership(
+    mut npc_query: Query<(&Transform, &mut ChunkCoord), (With<NpcController>, Changed<Transform>)>,
+) {
+    for (transform, mut chunk) in npc_query.iter_mut() {
+        let current = ChunkCoord::from_position(transform.translation.truncate());
+
+        if *chunk != current {
+            *chunk = current;
+        }
+    }
+}
+
+/// Loads the chunks around the player and unloads the ones that are too far away.
+pub fn stream_chunks(
+    mut commands: Commands,
+    environment_assets: Res<EnvironmentAssets>,
+    mut game_sprites: ResMut<GameSprites>,
+    asset_server: Res<AssetServer>,
+    npc_pool: Res<NpcPool>,
+    biome_map: Res<BiomeMap>,
+    world_seed: Res<WorldSeed>,
+    world_manager: Res<WorldManager>,
+    structure_registry: Res<StructureRegistry>,
+    mut chunk_manager: ResMut<ChunkManager>,
+    mut placement_grid: ResMut<PlacementGrid>,
+    player_query: Query<&Transform, With<Player>>,
+    obstacle_query: Query<(
+        Entity,
+        &Obstacle,
+        &ChunkCoord,
+        &Transform,
+        Option<&Depleted>,
+    )>,
+    npc_query: Query<(
+        Entity,
+        &NpcId,
+        &ChunkCoord,
+        &Transform,
+        &Character,
+        &NpcController,
+    )>,
+    structure_query: Query<(
+        Entity,
+        &Structure,
+        &ChunkCoord,
+        &Transform,
+        Option<&Door>,
+        Option<&Inventory>,
+    )>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let center = ChunkCoord::from_position(player_transform.translation.truncate());
+
+    // Unloading
+    let unloaded = chunk_manager
+        .loaded_chunks()
+        .into_iter()
+        .filter(|chunk| chunk.distance(center) > UNLOAD_RADIUS)
+        .collect::<Vec<_>>();
+
+    for chunk in unloaded.iter() {
+        chunk_manager.loaded.remove(&chunk.0);
+        chunk_manager.stored.insert(chunk.0, StoredChunk::default());
+    }
+
+    if !unloaded.is_empty() {
+        for (entity, obstacle, chunk, transform, depleted) in obstacle_query.iter() {
+            if !unloaded.contains(chunk) {
+                continue;
+            }
+
+            if let Some(stored) = chunk_manager.stored.get_mut(&chunk.0) {
+                stored
+                    .obstacles
+                    .push(StoredObstacle::new(obstacle, transform, depleted));
+            }
+
+            commands.entity(entity).despawn_recursive();
+        }
+
+        for (entity, structure, chunk, transform, door, inventory) in structure_query.iter() {
+            if !unloaded.contains(chunk) {
+                continue;
+            }
+
+            if let Some(stored) = chunk_manager.stored.get_mut(&chunk.0) {
+                stored
+                    .structures
+                    .push(StoredStructure::new(structure, transform, door, inventory));
+            }
+
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    // NPCs are checked every frame, because they may wander off into a chunk that isn't loaded
+    for (entity, npc_id, chunk, transform, character, controller) in npc_query.iter() {
+        if chunk_manager.is_loaded(*chunk) {
+            continue;
+        }
+
+        if let Some(stored) = chunk_manager.stored.get_mut(&chunk.0) {
+            stored
+                .npcs
+                .push(StoredNpc::new(npc_id, transform, character, controller));
+        }
+
+        // NPCs that walked off into chunks which were never generated simply disappear
+        commands.entity(entity).despawn_recursive();
+    }
+
+    // Loading
+    load_chunks_around(
+        &mut commands,
+        &environment_assets,
+        &mut game_sprites,
+        &asset_server,
+        &npc_pool,
+        &biome_map,
+        &world_seed,
+        &structure_registry,
+        &mut chunk_manager,
+        &mut placement_grid,
+        center,
+        world_manager.difficulty,
+    );
+}
+
+/// Loads every chunk that's close enough to the given chunk and isn't loaded yet.
+pub fn load_chunks_around(
+    commands: &mut Commands,
+    environment_assets: &Res<EnvironmentAssets>,
+    game_sprites: &mut ResMut<GameSprites>,
+    asset_server: &Res<AssetServer>,
+    npc_pool: &Res<NpcPool>,
+    biome_map: &Res<BiomeMap>,
+    world_seed: &Res<WorldSeed>,
+    structure_registry: &Res<StructureRegistry>,
+    chunk_manager: &mut ResMut<ChunkManager>,
+    placement_grid: &mut ResMut<PlacementGrid>,
+    center: ChunkCoord,
+    difficulty: f32,
+) {
+    for x in -LOAD_RADIUS..=LOAD_RADIUS {
+        for y in -LOAD_RADIUS..=LOAD_RADIUS {
+            let chunk = ChunkCoord(center.0 + IVec2::new(x, y));
+
+            if chunk_manager.is_loaded(chunk) {
+                continue;
+            }
+
+            if let Some(stored) = chunk_manager.stored.remove(&chunk.0) {
+                restore_chunk(
+                    commands,
+                    environment_assets,
+                    game_sprites,
+                    asset_server,
+                    npc_pool,
+                    structure_registry,
+                    placement_grid,
+                    stored,
+                );
+            } else {
+                generate_chunk(
+                    commands,
+                    environment_assets,
+                    game_sprites,
+                    asset_server,
+                    npc_pool,
+                    biome_map,
+                    world_seed,
+                    placement_grid,
+                    chunk,
+                    difficulty,
+                );
+            }
+
+            chunk_manager.loaded.insert(chunk.0);
+        }
+    }
+}
+
+fn generate_chunk(
+    commands: &mut Commands,
+    environment_assets: &Res<EnvironmentAssets>,
+    game_sprites: &mut ResMut<GameSprites>,
+    asset_server: &Res<AssetServer>,
+    npc_pool: &Res<NpcPool>,
+    biome_map: &Res<BiomeMap>,
+    world_seed: &Res<WorldSeed>,
+    placement_grid: &mut ResMut<PlacementGrid>,
+    chunk: ChunkCoord,
+    difficulty: f32,
+) {
+    let mut rng = world_seed.chunk_rng(chunk.0);
+    let cells = (CHUNK_SIZE / GENERATION_CELL_SIZE) as i32;
+
+    for x in 0..cells {
+        for y in 0..cells {
+            let cell_origin = chunk.origin() + Vec2::new(x as f32, y as f32) * GENERATION_CELL_SIZE;
+            let cell_center = cell_origin + GENERATION_CELL_SIZE / 2.0;
+
+            if let Some(biome) = biome_map.biome_at(cell_center) {
+                populate_cell(
+                    commands,
+                    environment_assets,
+                    placement_grid,
+                    biome,
+                    cell_origin,
+                    &mut rng,
+                );
+            }
+        }
+    }
+
+    let npc_amount = biome::roll_density(&mut rng, NPC_DENSITY);
+    super::spawn_random_npcs(
+        commands,
+        game_sprites,
+        asset_server,
+        npc_pool,
+        biome_map,
+        placement_grid,
+        &mut rng,
+        chunk.area(),
+        npc_amount,
+        difficulty,
+        // Generated chunks don't depend on the time of day, so that they stay reproducible
+        None,
+    );
+}
+
+/// Scatters the obstacles of a biome over a single generation cell.
+fn populate_cell(
+    commands: &mut Commands,
+    environment_assets: &Res<EnvironmentAssets>,
+    placement_grid: &mut PlacementGrid,
+    biome: &BiomeData,
+    cell_origin: Vec2,
+    rng: &mut StdRng,
+) {
+    let cell_area = Rect::from_corners(cell_origin, cell_origin + GENERATION_CELL_SIZE);
+
+    let trees = biome::roll_density(rng, biome.tree_density);
+    let rocks = biome::roll_density(rng, biome.rock_density);
+
+    let obstacles = std::iter::repeat_n((ObstacleKind::Tree, &biome.tree_indices), trees).chain(
+        std::iter::repeat_n((ObstacleKind::Rock, &biome.rock_indices), rocks),
+    );
+
+    for (kind, sprite_indices) in obstacles {
+        let Some(sprite_index) = sprite_indices.choose(rng).copied() else {
+            continue;
+        };
+
+        // Crowded cells simply end up with less obstacles than the biome asked for
+        if let Some(position) = placement_grid.find_free_position(
+            rng,
+            cell_area,
+            kind.footprint_radius(),
+            placement::DEFAULT_ATTEMPTS,
+        ) {
+            let obstacle = Obstacle { kind, sprite_index };
+            spawn_obstacle(
+                commands,
+                environment_assets,
+                placement_grid,
+                obstacle,
+                position,
+            );
+        }
+    }
+}
+
+fn restore_chunk(
+    commands: &mut Commands,
+    environment_assets: &Res<EnvironmentAssets>,
+    game_sprites: &mut ResMut<GameSprites>,
+    asset_server: &Res<AssetServer>,
+    npc_pool: &Res<NpcPool>,
+    structure_registry: &Res<StructureRegistry>,
+    placement_grid: &mut PlacementGrid,
+    stored: StoredChunk,
+) {
+    for stored_obstacle in stored.obstacles {
+        let obstacle = spawn_obstacle(
+            commands,
+            environment_assets,
+            placement_grid,
+            stored_obstacle.obstacle,
+            stored_obstacle.position,
+        );
+
+        if let Some(regrow_in) = stored_obstacle.regrow_in {
+            commands.entity(obstacle).insert(Depleted {
+                regrow: Timer::from_seconds(regrow_in, TimerMode::Once),
+            });
+        }
+    }
+
+    for stored_structure in stored.structures {
+        match structure_registry.get(&stored_structure.id) {
+            Some(data) => {
+                building::restore_structure(
+                    commands,
+                    game_sprites,
+                    asset_server,
+                    placement_grid,
+                    data,
+                    stored_structure,
+                );
+            }
+            None => println!(
+                "could not restore the unknown structure \"{}\"",
+                stored_structure.id
+            ),
+        }
+    }
+
+    for stored_npc in stored.npcs {
+        if let Some(npc_data) = npc_pool.get(&stored_npc.id) {
+            let npc = npc_data.load_entity(
+                commands,
+                game_sprites,
+                asset_server,
+                &NpcSpawnInfo {
+                    position: stored_npc.position,
+                    wander_delay: stored_npc.controller.target_change.duration().as_secs_f32(),
+                },
+            );
+
+            commands
+                .entity(npc)
+                .insert((stored_npc.character, stored_npc.controller));
+        }
+    }
+}
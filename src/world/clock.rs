@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::character::npc::NpcKind;
+
+use super::WorldObject;
+
+/// How long a full day lasts when the world data doesn't say, in seconds.
+const DEFAULT_DAY_LENGTH: f32 = 600.0;
+
+/// The point of the day a new world starts at, as a fraction of the day.
+const START_TIME: f32 = 0.15;
+
+/// Where each part of the day begins, as a fraction of the day. The day starts with dawn.
+const DAY_START: f32 = 0.1;
+const DUSK_START: f32 = 0.55;
+const NIGHT_START: f32 = 0.65;
+
+/// How dark the scene gets in the middle of the night.
+const MAX_DARKNESS: f32 = 0.6;
+const NIGHT_TINT: Color = Color::rgb(0.02, 0.02, 0.12);
+
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeOfDay {
+    Dawn,
+    Day,
+    Dusk,
+    Night,
+}
+
+impl TimeOfDay {
+    /// How likely an NPC of the given kind is picked when spawning at this time of day, compared
+    /// to the other NPCs that could spawn.
+    pub fn spawn_weight(&self, kind: &NpcKind) -> f32 {
+        match (self, kind) {
+            (TimeOfDay::Day, NpcKind::Friendly) => 3.0,
+            (TimeOfDay::Day, NpcKind::VeryHostile) => 0.5,
+            (TimeOfDay::Night, NpcKind::Friendly) => 0.5,
+            (TimeOfDay::Night, NpcKind::Hostile | NpcKind::VeryHostile) => 3.0,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Keeps track of the in-game time.
+#[derive(Resource, Reflect, Clone, Serialize, Deserialize)]
+pub struct WorldClock {
+    /// How long a full day lasts, in seconds.
+    pub day_length: f32,
+    /// The time passed since the first day started, in seconds.
+    elapsed: f32,
+}
+
+impl Default for WorldClock {
+    fn default() -> Self {
+        Self::new(DEFAULT_DAY_LENGTH)
+    }
+}
+
+impl WorldClock {
+    pub fn new(day_length: f32) -> Self {
+        Self {
+            day_length,
+            elapsed: START_TIME * day_length,
+        }
+    }
+
+    pub fn advance(&mut self, seconds: f32) {
+        self.elapsed += seconds;
+    }
+
+    /// The current day, starting at 1.
+    pub fn day(&self) -> u32 {
+        (self.elapsed / self.day_length) as u32 + 1
+    }
+
+    /// How far the current day has progressed, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        (self.elapsed / self.day_length).fract()
+    }
+
+    pub fn time_of_day(&self) -> TimeOfDay {
+        match self.progress() {
+            progress if progress < DAY_START => TimeOfDay::Dawn,
+            progress if progress < DUSK_START => TimeOfDay::Day,
+            progress if progress < NIGHT_START => TimeOfDay::Dusk,
+            _ => TimeOfDay::Night,
+        }
+    }
+
+    /// How dark the scene is, from 0 (broad daylight) to 1 (the middle of the night).
+    /// It fades in over the dusk and out over the dawn.
+    pub fn darkness(&self) -> f32 {
+        let progress = self.progress();
+
+        match self.time_of_day() {
+            TimeOfDay::Dawn => 1.0 - progress / DAY_START,
+            TimeOfDay::Day => 0.0,
+            TimeOfDay::Dusk => (progress - DUSK_START) / (NIGHT_START - DUSK_START),
+            TimeOfDay::Night => 1.0,
+        }
+    }
+}
+
+/// Darkens the whole screen at night.
+#[derive(Component)]
+pub struct NightOverlay;
+
+pub fn advance_clock(mut world_clock: ResMut<WorldClock>, time: Res<Time>) {
+    world_clock.advance(time.delta_seconds());
+}
+
+pub fn spawn_night_overlay(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..Default::default()
+            },
+            background_color: NIGHT_TINT.with_a(0.0).into(),
+            // Below every other UI, so that menus and the HUD stay readable at night
+            z_index: ZIndex::Global(-1),
+            ..Default::default()
+        },
+        Name::new("Night Overlay"),
+        NightOverlay,
+        WorldObject,
+    ));
+}
+
+pub fn update_night_overlay(
+    world_clock: Res<WorldClock>,
+    mut overlay_query: Query<&mut BackgroundColor, With<NightOverlay>>,
+) {
+    for mut background_color in overlay_query.iter_mut() {
+        background_color.0 = NIGHT_TINT.with_a(world_clock.darkness() * MAX_DARKNESS);
+    }
+}
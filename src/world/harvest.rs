@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    animation::{HitFlashEvent, VanishEvent},
+    asset::GameSprites,
+    character::player::Player,
+    combat::{started_collision, Projectile},
+    inventory::{pickup, ItemRegistry},
+};
+
+use super::{
+    obstacle::{Depleted, Harvestable, Obstacle},
+    placement::PlacementGrid,
+    seed::WorldRng,
+};
+
+/// How far from the obstacle the dropped resources are scattered.
+const DROP_SCATTER: f32 = 50.0;
+
+/// Damages obstacles hit by the player's projectiles, harvesting them once their health runs out.
+pub fn harvest_hits(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut game_sprites: ResMut<GameSprites>,
+    asset_server: Res<AssetServer>,
+    item_registry: Res<ItemRegistry>,
+    mut placement_grid: ResMut<PlacementGrid>,
+    mut world_rng: ResMut<WorldRng>,
+    projectile_query: Query<&Projectile>,
+    player_query: Query<(), With<Player>>,
+    mut obstacle_query: Query<
+        (&Obstacle, &mut Harvestable, &Transform, Option<&Children>),
+        Without<Depleted>,
+    >,
+    mut hit_flash_writer: EventWriter<HitFlashEvent>,
+    mut vanish_writer: EventWriter<VanishEvent>,
+) {
+    for event in collision_events.read() {
+        let Some((projectile_entity, obstacle_entity)) =
+            started_collision(event, |entity| projectile_query.contains(entity))
+        else {
+            continue;
+        };
+        let Ok(projectile) = projectile_query.get(projectile_entity) else {
+            continue;
+        };
+
+        // NPCs don't harvest, otherwise they would clear the map while fighting
+        if !player_query.contains(projectile.owner) {
+            continue;
+        }
+
+        let Ok((obstacle, mut harvestable, transform, children)) =
+            obstacle_query.get_mut(obstacle_entity)
+        else {
+            continue;
+        };
+
+        if harvestable.health <= 0.0 {
+            continue;
+        }
+
+        harvestable.health -= projectile.stats.damage;
+
+        // Trees keep their sprite on a child entity
+        match children {
+            Some(children) => {
+                for child in children.iter() {
+                    hit_flash_writer.send(HitFlashEvent { entity: *child });
+                }
+            }
+            None => hit_flash_writer.send(HitFlashEvent {
+                entity: obstacle_entity,
+            }),
+        }
+
+        if harvestable.health > 0.0 {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let (item_id, min, max) = obstacle.kind.drop();
+
+        if let Some(item) = item_registry.get(item_id) {
+            let amount = world_rng.spawning.gen_range(min..=max);
+            let offset = Vec2::new(
+                world_rng.spawning.gen_range(-DROP_SCATTER..DROP_SCATTER),
+                world_rng.spawning.gen_range(-DROP_SCATTER..DROP_SCATTER),
+            );
+
+            pickup::spawn_pickup(
+                &mut commands,
+                &mut game_sprites,
+                &asset_server,
+                item,
+                amount,
+                position + offset,
+            );
+        }
+
+        match obstacle.kind.regrow_time() {
+            Some(regrow_time) => {
+                commands.entity(obstacle_entity).insert(Depleted {
+                    regrow: Timer::from_seconds(regrow_time, TimerMode::Once),
+                });
+            }
+            None => {
+                // The obstacle is gone for good, so it shouldn't be stored with its chunk anymore
+                commands.entity(obstacle_entity).remove::<Obstacle>();
+                placement_grid.remove(position);
+                vanish_writer.send(VanishEvent {
+                    entity: obstacle_entity,
+                });
+            }
+        }
+    }
+}
+
+/// Hides obstacles that have just been depleted and turns off their collisions.
+pub fn hide_depleted(
+    mut commands: Commands,
+    depleted_query: Query<(Entity, Option<&Children>), Added<Depleted>>,
+) {
+    for (entity, children) in depleted_query.iter() {
+        commands
+            .entity(entity)
+            .insert(Visibility::Hidden)
+            .remove::<Collider>();
+
+        if let Some(children) = children {
+            for child in children.iter() {
+                commands.entity(*child).insert(Visibility::Hidden);
+            }
+        }
+    }
+}
+
+/// Brings depleted obstacles back once their regrow timer is up.
+pub fn regrow_obstacles(
+    mut commands: Commands,
+    mut depleted_query: Query<(
+        Entity,
+        &Obstacle,
+        &mut Depleted,
+        &mut Harvestable,
+        Option<&Children>,
+    )>,
+    time: Res<Time>,
+) {
+    for (entity, obstacle, mut depleted, mut harvestable, children) in depleted_query.iter_mut() {
+        depleted.regrow.tick(time.delta());
+
+        if !depleted.regrow.finished() {
+            continue;
+        }
+
+        harvestable.health = obstacle.kind.max_health();
+
+        commands
+            .entity(entity)
+            .remove::<Depleted>()
+            .insert((Visibility::Inherited, obstacle.kind.collider()));
+
+        if let Some(children) = children {
+            for child in children.iter() {
+                commands.entity(*child).insert(Visibility::Inherited);
+            }
+        }
+    }
+}
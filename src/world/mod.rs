@@ -0,0 +1,385 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset::{
+        biome::BiomeData,
+        npc::{NpcData, NpcSpawnInfo},
+        world::WorldData,
+        EnvironmentAssets, GameSprites, LoadEntity,
+    },
+    building::StructureRegistry,
+    character::player::{Player, PLAYER_SPAWN},
+    combat::faction::{self, FactionRegistry},
+    state::GameState,
+};
+
+use self::{
+    biome::BiomeMap,
+    chunk::{ChunkCoord, ChunkManager},
+    clock::{TimeOfDay, WorldClock},
+    navigation::NavGrid,
+    placement::PlacementGrid,
+    seed::{WorldRng, WorldSeed},
+};
+
+pub mod biome;
+pub mod chunk;
+pub mod clock;
+pub mod harvest;
+pub mod navigation;
+pub mod obstacle;
+pub mod placement;
+pub mod seed;
+
+pub struct WorldPlugin;
+
+/// The size of the square cells the world is split into while placing obstacles.
+/// Biome densities describe the amount of obstacles in a single cell.
+pub const GENERATION_CELL_SIZE: f32 = 250.0;
+
+/// The radius around the player spawn that's kept free of obstacles and NPCs.
+const SPAWN_CLEAR_RADIUS: f32 = 300.0;
+
+/// The id of the world data every world is created from.
+const DEFAULT_WORLD_DATA: &str = "default";
+
+/// The footprint used when looking for a free position for an NPC.
+/// It's large enough to fit the biggest NPCs.
+const NPC_SPAWN_RADIUS: f32 = 90.0;
+
+impl Plugin for WorldPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<WorldManager>()
+            .register_type::<WorldSeed>()
+            .register_type::<WorldClock>()
+            .register_type::<obstacle::Harvestable>()
+            .register_type::<obstacle::Depleted>()
+            .add_event::<ClearWorldEvent>()
+            .init_resource::<NpcPool>()
+            .add_systems(
+                OnEnter(GameState::PreparingWorld),
+                // The faction registry has to be inserted before the NPCs are checked against it
+                (apply_deferred, prepare_world)
+                    .chain()
+                    .after(faction::prepare_faction_registry),
+            )
+            .add_systems(
+                OnEnter(GameState::PreparingNpcs),
+                (populate_with_npcs, clock::spawn_night_overlay),
+            )
+            .add_systems(
+                Update,
+                (
+                    (
+                        npc_spawning,
+                        chunk::update_chunk_membership,
+                        chunk::stream_chunks,
+                    )
+                        .chain()
+                        .run_if(in_state(GameState::InGame)),
+                    (clock::advance_clock, clock::update_night_overlay)
+                        .run_if(in_state(GameState::InGame)),
+                    (navigation::track_nav_changes, navigation::rebuild_nav_grid)
+                        .chain()
+                        .run_if(in_state(GameState::InGame)),
+                    (
+                        harvest::harvest_hits,
+                        harvest::hide_depleted,
+                        harvest::regrow_obstacles,
+                    )
+                        .run_if(in_state(GameState::InGame)),
+                    clear_world_event,
+                ),
+            );
+    }
+}
+
+/// A Marker component that marks an entity as part of the world.
+/// Used to clear the game when entering the main menu.
+#[derive(Component)]
+pub struct WorldObject;
+
+#[derive(Event)]
+pub struct ClearWorldEvent;
+
+#[derive(Resource, Reflect, Clone, Serialize, Deserialize)]
+pub struct WorldManager {
+    spawn_timer: Timer,
+    difficulty: f32,
+}
+
+impl WorldManager {
+    pub fn difficulty(&self) -> f32 {
+        self.difficulty
+    }
+}
+
+#[derive(Resource)]
+pub struct NpcPool {
+    npcs: Vec<NpcData>,
+}
+
+impl Default for NpcPool {
+    fn default() -> Self {
+        Self { npcs: Vec::new() }
+    }
+}
+
+impl NpcPool {
+    pub fn get(&self, id: &str) -> Option<&NpcData> {
+        self.npcs.iter().find(|npc| npc.id == id)
+    }
+}
+
+pub fn prepare_world(
+    mut commands: Commands,
+    npcs: Res<Assets<NpcData>>,
+    biomes: Res<Assets<BiomeData>>,
+    faction_registry: Res<FactionRegistry>,
+    world_data: Res<Assets<WorldData>>,
+    world_seed: Res<WorldSeed>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    println!("preparing world with seed {}", world_seed.0);
+
+    let mut world_rng = WorldRng::new(&world_seed);
+
+    // Asset storage has no stable order, so the pool gets sorted to keep spawning reproducible
+    let mut pool = npcs.iter().map(|(_, npc)| npc.clone()).collect::<Vec<_>>();
+    pool.sort_by(|a, b| a.id.cmp(&b.id));
+
+    // NPCs of factions that didn't make it into the registry would have no collision group, so
+    // nothing could ever hit them
+    pool.retain(|npc| {
+        let faction_valid = faction_registry.get(&npc.faction).is_some();
+
+        if !faction_valid {
+            println!(
+                "skipping npc \"{}\": it belongs to an unknown or invalid faction \"{}\"",
+                npc.id, npc.faction
+            );
+        }
+
+        faction_valid
+    });
+
+    let biome_map = BiomeMap::new(
+        biomes.iter().map(|(_, biome)| biome.clone()).collect(),
+        &mut world_rng.generation,
+    );
+    biome_map.validate(&pool.iter().map(|npc| npc.id.as_str()).collect::<Vec<_>>());
+
+    commands.insert_resource(NpcPool { npcs: pool });
+    commands.insert_resource(biome_map);
+    commands.insert_resource(ChunkManager::default());
+
+    let mut placement_grid = PlacementGrid::default();
+    placement_grid.insert(PLAYER_SPAWN, SPAWN_CLEAR_RADIUS);
+    commands.insert_resource(placement_grid);
+
+    commands.insert_resource(WorldManager {
+        spawn_timer: Timer::from_seconds(20.0, TimerMode::Repeating),
+        difficulty: 0.0,
+    });
+    let world_clock = match world_data
+        .iter()
+        .find(|(_, data)| data.id == DEFAULT_WORLD_DATA)
+    {
+        Some((_, data)) if data.day_length > 0.0 => WorldClock::new(data.day_length),
+        Some(_) => {
+            println!("the day length has to be positive, using the default one");
+            WorldClock::default()
+        }
+        None => {
+            println!("could not find the world data, using the default day length");
+            WorldClock::default()
+        }
+    };
+    commands.insert_resource(world_clock);
+    commands.insert_resource(NavGrid::default());
+    commands.insert_resource(world_rng);
+
+    game_state.set(GameState::PreparingNpcs);
+}
+
+/// Generates the chunks around the player before the game starts, so that the player doesn't
+/// spawn into an empty world.
+pub fn populate_with_npcs(
+    mut commands: Commands,
+    environment_assets: Res<EnvironmentAssets>,
+    mut game_sprites: ResMut<GameSprites>,
+    npc_pool: Res<NpcPool>,
+    biome_map: Res<BiomeMap>,
+    asset_server: Res<AssetServer>,
+    world_seed: Res<WorldSeed>,
+    structure_registry: Res<StructureRegistry>,
+    mut chunk_manager: ResMut<ChunkManager>,
+    mut placement_grid: ResMut<PlacementGrid>,
+    player_query: Query<&Transform, With<Player>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let player_position = player_query
+        .get_single()
+        .map(|transform| transform.translation.truncate())
+        .unwrap_or(PLAYER_SPAWN);
+
+    chunk::load_chunks_around(
+        &mut commands,
+        &environment_assets,
+        &mut game_sprites,
+        &asset_server,
+        &npc_pool,
+        &biome_map,
+        &world_seed,
+        &structure_registry,
+        &mut chunk_manager,
+        &mut placement_grid,
+        ChunkCoord::from_position(player_position),
+        0.0,
+    );
+    game_state.set(GameState::InGame);
+}
+
+fn spawn_random_npcs(
+    commands: &mut Commands,
+    game_sprites: &mut ResMut<GameSprites>,
+    asset_server: &Res<AssetServer>,
+    npc_pool: &Res<NpcPool>,
+    biome_map: &Res<BiomeMap>,
+    placement_grid: &mut PlacementGrid,
+    rng: &mut StdRng,
+    area: Rect,
+    amount: usize,
+    difficulty: f32,
+    time_of_day: Option<TimeOfDay>,
+) {
+    let available_npcs = npc_pool
+        .npcs
+        .iter()
+        .filter(|npc| time_of_day.is_none_or(|time_of_day| npc.can_spawn_at(time_of_day)))
+        .filter(|npc| {
+            if let Some(max_diff) = npc.max_difficulty {
+                return difficulty >= npc.min_difficulty && difficulty <= max_diff;
+            }
+
+            difficulty >= npc.min_difficulty
+        })
+        .collect::<Vec<_>>();
+
+    // NPCs in the same batch keep their distance from each other. Their spots are only reserved
+    // while the batch is placed though, since NPCs walk away from where they spawned.
+    let mut reserved = Vec::new();
+
+    for _ in 0..amount {
+        let Some(position) =
+            placement_grid.place(rng, area, NPC_SPAWN_RADIUS, placement::DEFAULT_ATTEMPTS)
+        else {
+            continue;
+        };
+        reserved.push(position);
+
+        // Only NPCs that live in the biome at the chosen position can be picked
+        let eligible_npcs = available_npcs
+            .iter()
+            .filter(|npc| biome_map.allows_npc(position, &npc.id))
+            .collect::<Vec<_>>();
+
+        // The time of day makes some kinds of NPCs more likely to show up than others
+        let npc = eligible_npcs
+            .choose_weighted(rng, |npc| {
+                time_of_day.map_or(1.0, |time_of_day| time_of_day.spawn_weight(&npc.kind))
+            })
+            .ok();
+
+        if let Some(npc) = npc {
+            let wander_delay = rng.gen_range(5.0..15.0);
+
+            npc.load_entity(
+                commands,
+                game_sprites,
+                asset_server,
+                &NpcSpawnInfo {
+                    position,
+                    wander_delay,
+                },
+            );
+        }
+    }
+
+    for position in reserved {
+        placement_grid.remove(position);
+    }
+}
+
+fn clear_world_event(
+    mut commands: Commands,
+    world_object_query: Query<Entity, With<WorldObject>>,
+    mut clear_world_event: EventReader<ClearWorldEvent>,
+) {
+    for _ in clear_world_event.read() {
+        for world_entity in world_object_query.iter() {
+            commands.entity(world_entity).despawn_recursive();
+        }
+
+        commands.remove_resource::<ChunkManager>();
+        commands.remove_resource::<PlacementGrid>();
+    }
+}
+
+fn npc_spawning(
+    mut commands: Commands,
+    mut game_sprites: ResMut<GameSprites>,
+    npc_pool: Res<NpcPool>,
+    biome_map: Res<BiomeMap>,
+    chunk_manager: Res<ChunkManager>,
+    mut placement_grid: ResMut<PlacementGrid>,
+    asset_server: Res<AssetServer>,
+    world_clock: Res<WorldClock>,
+    time: Res<Time>,
+    player_query: Query<&Transform, With<Player>>,
+    mut world_manager: ResMut<WorldManager>,
+    mut world_rng: ResMut<WorldRng>,
+) {
+    world_manager.spawn_timer.tick(time.delta());
+    world_manager.difficulty += 0.0002;
+
+    if world_manager.spawn_timer.just_finished() {
+        let amount = world_rng.spawning.gen_range(1..=4usize);
+
+        println!(
+            "requesting to spawn {amount} entities. current difficulty: {}, time of day: {:?}",
+            world_manager.difficulty,
+            world_clock.time_of_day()
+        );
+
+        // New NPCs show up in one of the loaded chunks, but never right next to the player
+        let player_chunk = player_query
+            .get_single()
+            .map(|transform| ChunkCoord::from_position(transform.translation.truncate()))
+            .ok();
+
+        let chunks = chunk_manager
+            .loaded_chunks()
+            .into_iter()
+            .filter(|chunk| Some(*chunk) != player_chunk)
+            .collect::<Vec<_>>();
+
+        if let Some(chunk) = chunks.choose(&mut world_rng.spawning) {
+            spawn_random_npcs(
+                &mut commands,
+                &mut game_sprites,
+                &asset_server,
+                &npc_pool,
+                &biome_map,
+                &mut placement_grid,
+                &mut world_rng.spawning,
+                chunk.area(),
+                amount,
+                world_manager.difficulty,
+                Some(world_clock.time_of_day()),
+            );
+        }
+    }
+}
@@ -0,0 +1,346 @@
+//! Grid-based navigation for NPCs.
+//!
+//! The world is split into square cells, and every cell that's covered by a static collider
+//! (trees, rocks, structures) is blocked. NPCs that can't walk straight toward their target follow
+//! a path found with A* over the free cells instead.
+
+use std::{collections::BinaryHeap, time::Duration};
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_rapier2d::prelude::*;
+
+use crate::character::direction_to;
+
+/// The size of a navigation cell.
+pub const NAV_CELL_SIZE: f32 = 32.0;
+
+/// How far colliders are grown when blocking cells, so that NPCs don't try to squeeze through
+/// gaps they don't fit through.
+const NAV_CLEARANCE: f32 = 24.0;
+
+/// The least amount of time between rebuilds of the grid, in seconds.
+const REBUILD_INTERVAL: f32 = 0.5;
+
+/// The most cells a single path search looks at before it gives up. It keeps NPCs that chase
+/// unreachable targets from stalling the game.
+const MAX_SEARCH_CELLS: usize = 2500;
+
+/// How often NPCs plan their path again while following it, in seconds.
+const REPLAN_INTERVAL: f32 = 0.75;
+
+/// How far the target has to move away from the end of the path for it to be planned again.
+const REPLAN_DISTANCE: f32 = 2.0 * NAV_CELL_SIZE;
+
+/// How close NPCs have to get to a waypoint before moving on to the next one.
+const WAYPOINT_RADIUS: f32 = NAV_CELL_SIZE * 0.75;
+
+/// Marks static entities that NPCs have to walk around, e.g. trees, rocks and structures.
+/// Only their colliders end up in the grid, so that characters and projectiles coming and going
+/// don't cause rebuilds.
+#[derive(Component)]
+pub struct NavObstacle;
+
+/// The cells that are blocked by static colliders.
+#[derive(Resource)]
+pub struct NavGrid {
+    blocked: HashSet<IVec2>,
+    dirty: bool,
+    rebuild_timer: Timer,
+}
+
+impl Default for NavGrid {
+    fn default() -> Self {
+        Self {
+            blocked: HashSet::new(),
+            dirty: true,
+            rebuild_timer: Timer::from_seconds(REBUILD_INTERVAL, TimerMode::Once),
+        }
+    }
+}
+
+impl NavGrid {
+    pub fn cell(position: Vec2) -> IVec2 {
+        (position / NAV_CELL_SIZE).floor().as_ivec2()
+    }
+
+    pub fn cell_center(cell: IVec2) -> Vec2 {
+        (cell.as_vec2() + 0.5) * NAV_CELL_SIZE
+    }
+
+    pub fn is_blocked(&self, position: Vec2) -> bool {
+        self.blocked.contains(&Self::cell(position))
+    }
+
+    /// Whether a straight line between both positions only crosses free cells.
+    /// The cells at both ends don't count, since characters may stand right next to a collider.
+    pub fn is_clear_line(&self, from: Vec2, to: Vec2) -> bool {
+        let (start, end) = (Self::cell(from), Self::cell(to));
+        let steps = (from.distance(to) / (NAV_CELL_SIZE / 2.0)).ceil() as usize;
+
+        (1..steps).all(|step| {
+            let cell = Self::cell(from.lerp(to, step as f32 / steps as f32));
+            cell == start || cell == end || !self.blocked.contains(&cell)
+        })
+    }
+
+    /// Finds a path of waypoints from one position to the other with A*, or `None` if the target
+    /// can't be reached. The cells at both ends are treated as free, so that NPCs can find their
+    /// way to structures and out of tight spots.
+    pub fn find_path(&self, from: Vec2, to: Vec2) -> Option<Vec<Vec2>> {
+        let (start, goal) = (Self::cell(from), Self::cell(to));
+
+        let is_free = |cell: IVec2| cell == goal || cell == start || !self.blocked.contains(&cell);
+        // Octile distance, the exact cost of moving on an empty grid with diagonals
+        let heuristic = |cell: IVec2| {
+            let delta = (goal - cell).abs();
+            let (long, short) = (delta.max_element(), delta.min_element());
+            (long - short) as f32 + short as f32 * std::f32::consts::SQRT_2
+        };
+
+        let mut open = BinaryHeap::new();
+        let mut came_from = HashMap::<IVec2, IVec2>::new();
+        let mut costs = HashMap::<IVec2, f32>::new();
+
+        open.push(SearchNode {
+            cell: start,
+            estimate: heuristic(start),
+        });
+        costs.insert(start, 0.0);
+
+        let mut searched = 0;
+
+        while let Some(SearchNode { cell, .. }) = open.pop() {
+            if cell == goal {
+                return Some(Self::trace_path(&came_from, goal, to));
+            }
+
+            searched += 1;
+            if searched > MAX_SEARCH_CELLS {
+                return None;
+            }
+
+            let cost = costs[&cell];
+
+            for offset in NEIGHBOURS {
+                let next = cell + offset;
+
+                if !is_free(next) {
+                    continue;
+                }
+
+                // Diagonal moves may not cut the corners of blocked cells
+                let diagonal = offset.x != 0 && offset.y != 0;
+                if diagonal
+                    && (!is_free(cell + IVec2::new(offset.x, 0))
+                        || !is_free(cell + IVec2::new(0, offset.y)))
+                {
+                    continue;
+                }
+
+                let next_cost = cost
+                    + if diagonal {
+                        std::f32::consts::SQRT_2
+                    } else {
+                        1.0
+                    };
+
+                if costs.get(&next).is_some_and(|known| *known <= next_cost) {
+                    continue;
+                }
+
+                costs.insert(next, next_cost);
+                came_from.insert(next, cell);
+                open.push(SearchNode {
+                    cell: next,
+                    estimate: next_cost + heuristic(next),
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Walks back from the goal and turns the cells into waypoints, ending at the exact target.
+    /// The start cell is left out, since it's where the NPC already is.
+    fn trace_path(came_from: &HashMap<IVec2, IVec2>, goal: IVec2, target: Vec2) -> Vec<Vec2> {
+        let mut waypoints = Vec::new();
+        let mut cell = goal;
+
+        while let Some(previous) = came_from.get(&cell) {
+            waypoints.push(Self::cell_center(cell));
+            cell = *previous;
+        }
+
+        waypoints.reverse();
+
+        match waypoints.last_mut() {
+            Some(last) => *last = target,
+            None => waypoints.push(target),
+        }
+
+        waypoints
+    }
+}
+
+const NEIGHBOURS: [IVec2; 8] = [
+    IVec2::new(1, 0),
+    IVec2::new(-1, 0),
+    IVec2::new(0, 1),
+    IVec2::new(0, -1),
+    IVec2::new(1, 1),
+    IVec2::new(1, -1),
+    IVec2::new(-1, 1),
+    IVec2::new(-1, -1),
+];
+
+/// A cell waiting to be searched. The heap pops the cell with the lowest estimate first.
+struct SearchNode {
+    cell: IVec2,
+    estimate: f32,
+}
+
+impl PartialEq for SearchNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.estimate == other.estimate
+    }
+}
+
+impl Eq for SearchNode {}
+
+impl PartialOrd for SearchNode {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SearchNode {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+/// The path an NPC is following. It's kept until the target moves too far away from its end.
+#[derive(Component)]
+pub struct NpcPath {
+    waypoints: Vec<Vec2>,
+    /// Where the target was when the path was planned.
+    goal: Option<Vec2>,
+    replan: Timer,
+}
+
+impl Default for NpcPath {
+    fn default() -> Self {
+        Self {
+            waypoints: Vec::new(),
+            goal: None,
+            replan: Timer::from_seconds(REPLAN_INTERVAL, TimerMode::Once),
+        }
+    }
+}
+
+impl NpcPath {
+    pub fn clear(&mut self) {
+        self.waypoints.clear();
+        self.goal = None;
+    }
+
+    /// Returns the direction the NPC should move in to get to the target, planning a new path
+    /// when needed. NPCs walk straight toward targets they can see.
+    pub fn steer(
+        &mut self,
+        nav_grid: &NavGrid,
+        position: Vec2,
+        target: Vec2,
+        delta: Duration,
+    ) -> Vec2 {
+        self.replan.tick(delta);
+
+        if nav_grid.is_clear_line(position, target) {
+            self.clear();
+            return direction_to(position, target);
+        }
+
+        let target_moved = self
+            .goal
+            .is_none_or(|goal| goal.distance(target) > REPLAN_DISTANCE);
+
+        // Failed searches aren't repeated until it's time to plan again
+        let stale = target_moved || self.waypoints.is_empty();
+
+        if self.goal.is_none() || (stale && self.replan.finished()) {
+            self.waypoints = nav_grid.find_path(position, target).unwrap_or_default();
+            self.goal = Some(target);
+            self.replan.reset();
+        }
+
+        while self
+            .waypoints
+            .first()
+            .is_some_and(|waypoint| waypoint.distance(position) < WAYPOINT_RADIUS)
+        {
+            self.waypoints.remove(0);
+        }
+
+        // Without a path the NPC tries its luck on a straight line
+        let next = self.waypoints.first().copied().unwrap_or(target);
+        direction_to(position, next)
+    }
+}
+
+/// Marks the grid for a rebuild whenever the collider of an obstacle appears or disappears.
+pub fn track_nav_changes(
+    mut nav_grid: ResMut<NavGrid>,
+    added_query: Query<(), (With<NavObstacle>, Added<Collider>)>,
+    obstacle_query: Query<(), With<NavObstacle>>,
+    mut removed_colliders: RemovedComponents<Collider>,
+    mut removed_obstacles: RemovedComponents<NavObstacle>,
+) {
+    // Obstacles that stop blocking (e.g. opened doors) only lose their collider, while despawned
+    // obstacles lose their marker as well
+    let collider_removed = removed_colliders
+        .read()
+        .filter(|entity| obstacle_query.contains(*entity))
+        .count()
+        > 0;
+    let obstacle_removed = removed_obstacles.read().count() > 0;
+
+    if collider_removed || obstacle_removed || !added_query.is_empty() {
+        nav_grid.dirty = true;
+    }
+}
+
+/// Blocks every cell covered by the collider of an obstacle, at most every [`REBUILD_INTERVAL`]
+/// seconds.
+pub fn rebuild_nav_grid(
+    mut nav_grid: ResMut<NavGrid>,
+    collider_query: Query<(&Collider, &GlobalTransform), (With<NavObstacle>, Without<Sensor>)>,
+    time: Res<Time>,
+) {
+    nav_grid.rebuild_timer.tick(time.delta());
+
+    if !nav_grid.dirty || !nav_grid.rebuild_timer.finished() {
+        return;
+    }
+
+    let mut blocked = HashSet::new();
+
+    for (collider, transform) in collider_query.iter() {
+        let aabb = collider.raw.compute_local_aabb();
+        let center = transform.translation().truncate();
+        let min = NavGrid::cell(center + Vec2::new(aabb.mins.x, aabb.mins.y) - NAV_CLEARANCE);
+        let max = NavGrid::cell(center + Vec2::new(aabb.maxs.x, aabb.maxs.y) + NAV_CLEARANCE);
+
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                blocked.insert(IVec2::new(x, y));
+            }
+        }
+    }
+
+    nav_grid.blocked = blocked;
+    nav_grid.dirty = false;
+    nav_grid.rebuild_timer.reset();
+}
@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{animation::WobbleBundle, asset::EnvironmentAssets};
+
+use super::{chunk::ChunkCoord, navigation::NavObstacle, placement::PlacementGrid, WorldObject};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ObstacleKind {
+    Tree,
+    Rock,
+}
+
+impl ObstacleKind {
+    /// The radius around the obstacle that other placed objects have to keep clear.
+    pub fn footprint_radius(&self) -> f32 {
+        match self {
+            ObstacleKind::Tree => 70.0,
+            ObstacleKind::Rock => 40.0,
+        }
+    }
+
+    pub fn collider(&self) -> Collider {
+        match self {
+            ObstacleKind::Tree => Collider::cuboid(52.0, 2.0),
+            ObstacleKind::Rock => Collider::ball(24.0),
+        }
+    }
+
+    /// How much damage the obstacle takes before it's harvested.
+    pub fn max_health(&self) -> f32 {
+        match self {
+            ObstacleKind::Tree => 20.0,
+            ObstacleKind::Rock => 30.0,
+        }
+    }
+
+    /// The id of the item dropped when the obstacle is harvested, along with the minimum and
+    /// maximum amount.
+    pub fn drop(&self) -> (&'static str, u32, u32) {
+        match self {
+            ObstacleKind::Tree => ("wood", 2, 4),
+            ObstacleKind::Rock => ("stone", 1, 3),
+        }
+    }
+
+    /// How long a harvested obstacle takes to grow back, in seconds.
+    /// Obstacles without a regrow time are gone for good once harvested.
+    pub fn regrow_time(&self) -> Option<f32> {
+        match self {
+            ObstacleKind::Tree => Some(240.0),
+            ObstacleKind::Rock => None,
+        }
+    }
+}
+
+/// Marks the root entity of a tree or rock, so that it can be stored when its chunk unloads.
+#[derive(Component, Clone, Copy, Serialize, Deserialize)]
+pub struct Obstacle {
+    pub kind: ObstacleKind,
+    pub sprite_index: usize,
+}
+
+/// The health of an obstacle that can be harvested for resources.
+#[derive(Reflect, Component)]
+pub struct Harvestable {
+    pub health: f32,
+}
+
+/// Marks an obstacle that has been harvested and is waiting to grow back.
+/// Depleted obstacles are hidden and don't collide with anything.
+#[derive(Reflect, Component)]
+pub struct Depleted {
+    pub regrow: Timer,
+}
+
+/// An obstacle that is kept in memory while the chunk it belongs to is unloaded.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StoredObstacle {
+    pub obstacle: Obstacle,
+    pub position: Vec2,
+    /// The time left until a harvested obstacle grows back, in seconds.
+    #[serde(default)]
+    pub regrow_in: Option<f32>,
+}
+
+impl StoredObstacle {
+    pub fn new(obstacle: &Obstacle, transform: &Transform, depleted: Option<&Depleted>) -> Self {
+        Self {
+            obstacle: *obstacle,
+            position: transform.translation.truncate(),
+            regrow_in: depleted.map(|depleted| depleted.regrow.remaining_secs()),
+        }
+    }
+}
+
+pub fn spawn_obstacle(
+    commands: &mut Commands,
+    environment_assets: &Res<EnvironmentAssets>,
+    placement_grid: &mut PlacementGrid,
+    obstacle: Obstacle,
+    position: Vec2,
+) -> Entity {
+    placement_grid.insert(position, obstacle.kind.footprint_radius());
+
+    let entity = match obstacle.kind {
+        ObstacleKind::Tree => spawn_tree(
+            commands,
+            environment_assets,
+            obstacle.sprite_index,
+            position,
+        ),
+        ObstacleKind::Rock => spawn_rock(
+            commands,
+            environment_assets,
+            obstacle.sprite_index,
+            position,
+        ),
+    };
+
+    commands.entity(entity).insert((
+        obstacle,
+        Harvestable {
+            health: obstacle.kind.max_health(),
+        },
+        ChunkCoord::from_position(position),
+        NavObstacle,
+    ));
+
+    entity
+}
+
+fn spawn_tree(
+    commands: &mut Commands,
+    environment_assets: &Res<EnvironmentAssets>,
+    sprite_index: usize,
+    position: Vec2,
+) -> Entity {
+    let tree = commands
+        .spawn(SpriteSheetBundle {
+            sprite: TextureAtlasSprite {
+                index: sprite_index,
+                custom_size: Some(Vec2::new(384.0, 384.0)),
+                ..Default::default()
+            },
+            texture_atlas: environment_assets.tree_atlas.clone(),
+            transform: Transform::from_xyz(20.0, 184.0, 0.0),
+            global_transform: GlobalTransform::default(),
+            ..Default::default()
+        })
+        .insert(WorldObject)
+        .insert(WobbleBundle::new(Vec3::ONE))
+        .id();
+
+    commands
+        .spawn(RigidBody::Fixed)
+        .insert(ObstacleKind::Tree.collider())
+        .insert(GlobalTransform::default())
+        .insert(Transform::from_translation(position.extend(2.0)))
+        .insert(WorldObject)
+        .insert(InheritedVisibility::default())
+        .push_children(&[tree])
+        .id()
+}
+
+fn spawn_rock(
+    commands: &mut Commands,
+    environment_assets: &Res<EnvironmentAssets>,
+    sprite_index: usize,
+    position: Vec2,
+) -> Entity {
+    commands
+        .spawn(SpriteSheetBundle {
+            sprite: TextureAtlasSprite {
+                index: sprite_index,
+                custom_size: Some(Vec2::new(64.0, 64.0)),
+                ..Default::default()
+            },
+            texture_atlas: environment_assets.rock_atlas.clone(),
+            transform: Transform::from_translation(position.extend(-2.0)),
+            global_transform: GlobalTransform::default(),
+            ..Default::default()
+        })
+        .insert(RigidBody::Fixed)
+        .insert(ObstacleKind::Rock.collider())
+        .insert(WorldObject)
+        .insert(WobbleBundle::new(Vec3::ONE))
+        .id()
+}
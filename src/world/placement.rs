@@ -0,0 +1,115 @@
+use bevy::{prelude::*, utils::HashMap};
+use rand::prelude::*;
+
+/// The size of the cells footprints are sorted into.
+/// It should be at least as large as the biggest footprint, so that looking up the neighbouring
+/// cells is enough to find every overlap.
+const CELL_SIZE: f32 = 128.0;
+
+/// How many random positions are tried before giving up on placing an object.
+pub const DEFAULT_ATTEMPTS: usize = 30;
+
+/// A circle that's occupied by a placed object.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Footprint {
+    position: Vec2,
+    radius: f32,
+}
+
+/// Keeps track of the space taken by placed objects, so that new objects never overlap them.
+///
+/// Positions are found with dart throwing: random points are picked until one of them keeps the
+/// minimum distance to everything around it, which results in a Poisson-disk distribution.
+/// Footprints stay registered while their chunk is unloaded, so that a freshly generated chunk
+/// can't place anything on top of the objects stored in its neighbours.
+#[derive(Resource, Default)]
+pub struct PlacementGrid {
+    cells: HashMap<IVec2, Vec<Footprint>>,
+    largest_radius: f32,
+}
+
+impl PlacementGrid {
+    fn cell(position: Vec2) -> IVec2 {
+        (position / CELL_SIZE).floor().as_ivec2()
+    }
+
+    /// Checks whether a circle of the given radius fits at the position without overlapping
+    /// anything that has been placed already.
+    pub fn is_free(&self, position: Vec2, radius: f32) -> bool {
+        let reach = ((radius + self.largest_radius) / CELL_SIZE).ceil() as i32;
+        let center = Self::cell(position);
+
+        for x in -reach..=reach {
+            for y in -reach..=reach {
+                if let Some(footprints) = self.cells.get(&(center + IVec2::new(x, y))) {
+                    let overlaps = footprints.iter().any(|footprint| {
+                        footprint.position.distance(position) < footprint.radius + radius
+                    });
+
+                    if overlaps {
+                        return false;
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Marks a circle as occupied.
+    /// Inserting the same footprint twice (e.g. when a stored chunk is loaded again) has no effect.
+    pub fn insert(&mut self, position: Vec2, radius: f32) {
+        let footprint = Footprint { position, radius };
+        let cell = self.cells.entry(Self::cell(position)).or_default();
+
+        if !cell.contains(&footprint) {
+            cell.push(footprint);
+            self.largest_radius = self.largest_radius.max(radius);
+        }
+    }
+
+    /// Frees the space taken by the object placed at the given position.
+    pub fn remove(&mut self, position: Vec2) {
+        if let Some(cell) = self.cells.get_mut(&Self::cell(position)) {
+            cell.retain(|footprint| footprint.position != position);
+        }
+    }
+
+    /// Looks for a random position inside the area where a circle of the given radius fits.
+    /// Returns [`None`] if no such position was found within the given amount of attempts.
+    pub fn find_free_position(
+        &self,
+        rng: &mut StdRng,
+        area: Rect,
+        radius: f32,
+        attempts: usize,
+    ) -> Option<Vec2> {
+        for _ in 0..attempts {
+            let position = Vec2::new(
+                rng.gen_range(area.min.x..area.max.x),
+                rng.gen_range(area.min.y..area.max.y),
+            );
+
+            if self.is_free(position, radius) {
+                return Some(position);
+            }
+        }
+
+        None
+    }
+
+    /// Finds a free position like [`PlacementGrid::find_free_position`] and immediately marks it
+    /// as occupied.
+    pub fn place(
+        &mut self,
+        rng: &mut StdRng,
+        area: Rect,
+        radius: f32,
+        attempts: usize,
+    ) -> Option<Vec2> {
+        let position = self.find_free_position(rng, area, radius, attempts)?;
+        self.insert(position, radius);
+
+        Some(position)
+    }
+}
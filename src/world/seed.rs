@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+// Random number streams derived from the `WorldSeed`.
+// Every subsystem draws from its own stream, so that e.g. an NPC wandering around more often
+// doesn't change which NPCs get spawned later.
+const GENERATION_STREAM: u64 = 0;
+const SPAWNING_STREAM: u64 = 1;
+const AI_STREAM: u64 = 2;
+/// Chunks get their own streams, starting at this offset, so that the random draws for a chunk
+/// don't depend on how many chunks were generated before it. Its content still can: placement
+/// near the borders checks the neighbouring chunks, and the NPCs picked depend on the difficulty
+/// at the time.
+const CHUNK_STREAM_OFFSET: u64 = 0x1000;
+
+/// The seed that drives every random decision made by the world, NPC and AI code.
+/// Playing twice with the same seed results in the same trees, rocks, NPC placement and wander
+/// targets.
+#[derive(Resource, Reflect, Clone, Copy, Debug)]
+pub struct WorldSeed(pub u64);
+
+impl WorldSeed {
+    pub fn random() -> Self {
+        Self(rand::random())
+    }
+
+    /// Turns the text typed in by the player into a seed.
+    /// Numbers are used as-is, so that a seed printed in a bug report can be typed back in.
+    /// Any other text gets hashed with FNV-1a, which (unlike the std hasher) is stable between
+    /// builds.
+    pub fn from_text(text: &str) -> Self {
+        let text = text.trim();
+
+        if let Ok(seed) = text.parse::<u64>() {
+            return Self(seed);
+        }
+
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in text.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+
+        Self(hash)
+    }
+
+    /// Creates an independent random number generator for the given stream.
+    pub fn rng(&self, stream: u64) -> StdRng {
+        StdRng::seed_from_u64(self.0 ^ stream.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    }
+
+    /// Creates the random number generator used for generating the given chunk.
+    pub fn chunk_rng(&self, chunk: IVec2) -> StdRng {
+        let packed = ((chunk.x as u32 as u64) << 32) | chunk.y as u32 as u64;
+        self.rng(packed.wrapping_add(CHUNK_STREAM_OFFSET))
+    }
+}
+
+/// Looks for a `--seed <seed>` argument on the command line.
+pub fn seed_argument() -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--seed");
+    args.next();
+    args.next()
+}
+
+/// The random number generators used while the game is running.
+/// They get recreated from the [`WorldSeed`] every time a new world is prepared.
+#[derive(Resource)]
+pub struct WorldRng {
+    /// Used for setting up the world generation, e.g. seeding the biome noise.
+    pub generation: StdRng,
+    /// Used for picking which NPCs spawn and where.
+    pub spawning: StdRng,
+    /// Used by the NPC AI and by attacks, e.g. for choosing wander targets and rolling spread.
+    pub ai: StdRng,
+}
+
+impl WorldRng {
+    pub fn new(seed: &WorldSeed) -> Self {
+        Self {
+            generation: seed.rng(GENERATION_STREAM),
+            spawning: seed.rng(SPAWNING_STREAM),
+            ai: seed.rng(AI_STREAM),
+        }
+    }
+}